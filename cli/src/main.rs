@@ -4,42 +4,117 @@
  */
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::Arc;
 
 use clap::Parser;
-use tokio::io::Result;
 
 use options::Options;
+use shared_library::{
+    source_for_paths, Corpus, Fortune, FortuneError, FortuneSource, FortuneStats, Manifest, Result,
+};
+#[cfg(feature = "embedded")]
+use shared_library::{EmbeddedSource, LayeredSource};
 
 mod options;
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let options = Options::parse();
-    return if let Some(folder) = options.folder() {
-        if options.summary() {
-            match show_summary(&folder).await {
-                Err(error) => {
-                    eprintln!("Error: {}", error);
-                    ExitCode::from(1)
-                }
-                _ => ExitCode::default(),
+    let folders = options.folders();
+    // The built in fortunes don't need a data folder, but checking does
+    if folders.is_empty() && (options.check() || !cfg!(feature = "embedded")) {
+        eprintln!("Can't find default data folder");
+        return ExitCode::from(100);
+    }
+    if options.next().is_some() && options.cursors().is_none() {
+        eprintln!("Can't find default state folder");
+        return ExitCode::from(100);
+    }
+    if options.check() {
+        return check(&folders).await;
+    }
+    let result = if options.summary() {
+        show_summary(&folders, &options).await
+    } else if options.search() {
+        show_matches(&folders, &options).await
+    } else {
+        show_fortune(&folders, &options).await
+    };
+    match result {
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            exit_code(&error)
+        }
+        _ => ExitCode::default(),
+    }
+}
+/// Checks the fortune files in the folders, and shows each problem found as a line of JSON.
+/// Exits with code 9 if there are any.
+async fn check(folders: &[PathBuf]) -> ExitCode {
+    match shared_library::validate(folders).await {
+        Ok(issues) => {
+            for issue in &issues {
+                println!("{}", serde_json::to_string(issue).unwrap_or_default());
             }
-        } else {
-            match show_fortune(&folder, options.verbose()).await {
-                Err(error) => {
-                    eprintln!("Error: {}", error);
-                    ExitCode::from(1)
-                }
-                _ => ExitCode::default(),
+            if issues.is_empty() {
+                ExitCode::default()
+            } else {
+                ExitCode::from(9)
             }
         }
-    } else {
-        eprintln!("Can't find default data folder");
-        ExitCode::from(100)
-    };
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            exit_code(&error)
+        }
+    }
+}
+/// Returns a distinct exit code for each kind of error.
+fn exit_code(error: &FortuneError) -> ExitCode {
+    match error {
+        FortuneError::Io { .. } => ExitCode::from(1),
+        FortuneError::DataFolder { .. } => ExitCode::from(2),
+        FortuneError::EmptyCorpus { .. } => ExitCode::from(3),
+        FortuneError::BadVersion { .. }
+        | FortuneError::TruncatedHeader { .. }
+        | FortuneError::OffsetOutOfRange { .. }
+        | FortuneError::InvalidManifest { .. } => ExitCode::from(4),
+        FortuneError::MissingTextFile { .. } => ExitCode::from(5),
+        FortuneError::InvalidEncoding { .. } | FortuneError::UnknownEncoding { .. } => {
+            ExitCode::from(6)
+        }
+        FortuneError::InvalidSource { .. }
+        | FortuneError::UnknownSource { .. }
+        | FortuneError::PercentOver { .. }
+        | FortuneError::InvalidPattern { .. }
+        | FortuneError::InvalidQuery { .. }
+        | FortuneError::InvalidId { .. }
+        | FortuneError::NotOrdered { .. } => ExitCode::from(7),
+        FortuneError::NoSuchFortune { .. } => ExitCode::from(8),
+    }
+}
+/// Returns the source of the fortunes in the data folders. With the `embedded` feature, they
+/// are layered over the fortunes built into the program, so a data folder can override or
+/// extend them, and the built in fortunes are used on their own if no data folder exists.
+fn fortune_source(folders: &[PathBuf]) -> Result<Arc<dyn FortuneSource>> {
+    #[cfg(feature = "embedded")]
+    {
+        let embedded: Arc<dyn FortuneSource> = Arc::new(EmbeddedSource::new());
+        if folders.is_empty() {
+            return Ok(embedded);
+        }
+        Ok(Arc::new(LayeredSource::new(vec![
+            source_for_paths(folders)?,
+            embedded,
+        ])?))
+    }
+    #[cfg(not(feature = "embedded"))]
+    source_for_paths(folders)
 }
-async fn show_summary(folder: &PathBuf) -> Result<()> {
-    let fortune_stats = shared_library::fortune_stats(folder).await?;
+async fn show_summary(folders: &[PathBuf], options: &Options) -> Result<()> {
+    let fortune_options = options.fortune_options()?;
+    let fortune_stats = Corpus::load_source(fortune_source(folders)?, &fortune_options)
+        .await?
+        .fortune_stats(&fortune_options)?;
     let total = fortune_stats.iter().fold(0, |total, fortune_file_info| {
         total + fortune_file_info.count()
     });
@@ -50,23 +125,113 @@ async fn show_summary(folder: &PathBuf) -> Result<()> {
         fortune_stats.len(),
         if fortune_stats.len() == 1 { "" } else { "s" },
     );
-    println!("{}", folder.display());
+    for folder in folders {
+        println!("{}", folder.display());
+    }
+    #[cfg(feature = "embedded")]
+    println!("{}", EmbeddedSource::new().path().display());
     let longest = &fortune_stats.iter().fold(0, |acc, fortune_file_info| {
-        let file = fortune_file_info.file();
+        let file = display_name(fortune_file_info);
         std::cmp::max(acc, file.len())
     });
     for fortune_status in &fortune_stats {
-        let file_name = fortune_status.file();
-        let percent = ((fortune_status.count() * 100) as f64) / (total as f64);
-        println!("\t{:width$} {:.2}%", file_name, percent, width = longest);
+        let file_name = display_name(fortune_status);
+        let percent = fortune_status.percent();
+        let manifest = fortune_status.manifest();
+        match manifest.title() {
+            Some(title) => println!(
+                "\t{:width$} {:6.2}%  {}",
+                file_name,
+                percent,
+                title,
+                width = longest
+            ),
+            None => println!("\t{:width$} {:6.2}%", file_name, percent, width = longest),
+        }
+        if options.verbose() {
+            show_manifest(manifest);
+        }
+    }
+    Ok(())
+}
+/// Shows the metadata from a fortune file's manifest, other than its title, one field per line.
+fn show_manifest(manifest: &Manifest) {
+    let fields = [
+        ("Description", manifest.description()),
+        ("Language", manifest.language()),
+        ("License", manifest.license()),
+        ("Source", manifest.source()),
+        ("Weight", manifest.weight().map(|weight| weight.to_string())),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("\t\t{}: {}", name, value);
+        }
+    }
+}
+/// Returns the file's name, prefixed with its category, and with its folder if it is offensive.
+fn display_name(fortune_stats: &FortuneStats) -> String {
+    let file = match fortune_stats.category() {
+        Some(category) => format!("{}/{}", category, fortune_stats.file()),
+        None => fortune_stats.file(),
+    };
+    if fortune_stats.is_offensive() {
+        format!("off/{}", file)
+    } else {
+        file
+    }
+}
+async fn show_fortune(data_paths: &[PathBuf], options: &Options) -> Result<()> {
+    let fortune_options = options.fortune_options()?;
+    let corpus = Corpus::load_source(fortune_source(data_paths)?, &fortune_options).await?;
+    let fortune = if let Some(id) = options.id() {
+        corpus.fortune_by_id(&id).await?
+    } else if let (Some(id), Some(cursors)) = (options.next(), options.cursors()) {
+        corpus.next_fortune(&id, &cursors, "").await?
+    } else if options.today() {
+        corpus
+            .fortune_of_the_day(&fortune_options, None, options.user().as_deref())
+            .await?
+    } else {
+        corpus.random_fortune(&fortune_options).await?
+    };
+    if options.verbose() {
+        println!("[{}]", fortune.id())
     }
+    println!("{}", fortune_text(&fortune, options));
     Ok(())
 }
-async fn show_fortune(data_path: &PathBuf, verbose: bool) -> Result<()> {
-    let fortune = shared_library::random_fortune(data_path).await?;
-    if verbose {
-        println!("[{}]", fortune.file())
+/// Shows all the fortunes that match the search pattern, query and author, the way BSD
+/// `fortune -m` does: the name of each file with matches, followed by the matching
+/// fortunes, each followed by a separator.
+async fn show_matches(data_paths: &[PathBuf], options: &Options) -> Result<()> {
+    let Some(search_options) = options.search_options()? else {
+        return Ok(());
+    };
+    let corpus =
+        Corpus::load_source(fortune_source(data_paths)?, &options.fortune_options()?).await?;
+    let mut search = corpus.search(&search_options)?;
+    let mut file = None;
+    while let Some(fortune) = search.next_match().await {
+        let fortune = fortune?;
+        if file.as_ref() != Some(&fortune.file()) {
+            println!("({})\n%", fortune.file());
+            file = Some(fortune.file());
+        }
+        if options.verbose() {
+            println!("[{}]", fortune.id())
+        }
+        println!("{}%", fortune_text(&fortune, options));
     }
-    println!("{}", fortune.fortune());
     Ok(())
 }
+/// Returns the text of the fortune, without its attribution if it is hidden, and laid out
+/// to fit the width, if there is one.
+fn fortune_text(fortune: &Fortune, options: &Options) -> String {
+    match (options.format_options(), options.hide_attribution()) {
+        (Some(format_options), true) => format_options.format_body(&fortune.body()),
+        (Some(format_options), false) => format_options.format(fortune),
+        (None, true) => fortune.body(),
+        (None, false) => fortune.fortune(),
+    }
+}
@@ -6,34 +6,282 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
+use shared_library::{
+    Collection, Cursors, Decoding, FormatOptions, FortuneOptions, Query, Result, SearchOptions,
+    Selection, Source,
+};
+
+/// The name of the state file that keeps the user's place in the files read with --next.
+const CURSORS_FILE: &str = "cursors.tsv";
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Options {
-    /// The fortune folder
-    folder: Option<PathBuf>,
+    /// The fortune files to choose from, each optionally preceded by a percentage,
+    /// e.g. `30% startrek 70% zippy`, or categories, e.g. `humor`
+    #[arg(value_name = "[N%] FILE")]
+    sources: Vec<String>,
+
+    /// A fortune folder. Repeat it to use more than one; if they have fortune files with the
+    /// same name, the ones in the earlier folder are used. The default is $FORTUNE_PATH
+    #[arg(short = 'd', long)]
+    folder: Vec<PathBuf>,
 
     /// Show information about the fortune files and exit
-    #[arg(short, long)]
+    #[arg(short = 'f', long)]
     summary: bool,
 
-    /// Also show the fortune's source file
+    /// Also show the fortune's identifier, which includes its source file. With --summary,
+    /// also show the metadata from the fortune files' manifests
     #[arg(short, long)]
     verbose: bool,
+
+    /// Choose from all the fortune files with equal probability, regardless of their sizes
+    #[arg(short, long)]
+    equal: bool,
+
+    /// Use only the offensive fortune files
+    #[arg(short, long, conflicts_with = "all")]
+    offensive: bool,
+
+    /// Use all the fortune files, both offensive and not
+    #[arg(short, long)]
+    all: bool,
+
+    /// Show all the fortunes that match the regular expression PATTERN, and exit
+    #[arg(short = 'm', long = "match", value_name = "PATTERN")]
+    pattern: Option<String>,
+
+    /// Ignore case when matching PATTERN
+    #[arg(short = 'i', long, requires = "pattern")]
+    ignore_case: bool,
+
+    /// Show all the fortunes that match QUERY, and exit. A query is words and quoted phrases
+    /// that must all be in a fortune, ignoring case and punctuation, with OR between
+    /// alternatives, e.g. `rust "memory safety" OR borrow checker`. Fortune files with word
+    /// indexes are searched faster
+    #[arg(long)]
+    query: Option<String>,
+
+    /// Show all the fortunes whose author's name includes AUTHOR, ignoring case, and exit.
+    /// With --match, show only the matching fortunes by AUTHOR
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Don't show the attribution at the end of a fortune, e.g. `-- Mark Twain`
+    #[arg(long)]
+    hide_attribution: bool,
+
+    /// Wrap fortunes to COLUMNS columns, leaving pre-formatted ones alone, or show them as they
+    /// are if it is 0 [default: the terminal's width, if the output is a terminal]
+    #[arg(short, long, value_name = "COLUMNS")]
+    width: Option<usize>,
+
+    /// Choose only short fortunes, i.e. those no longer than LENGTH
+    #[arg(short, long, conflicts_with = "long")]
+    short: bool,
+
+    /// Choose only long fortunes, i.e. those longer than LENGTH
+    #[arg(short, long)]
+    long: bool,
+
+    /// The longest fortune, in bytes, that is considered short
+    #[arg(short = 'n', long, value_name = "LENGTH", default_value_t = 160)]
+    length: usize,
+
+    /// Seed the random number generator, so the same seed chooses the same fortune
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Show the fortune of the day, which is the same all day
+    #[arg(long)]
+    today: bool,
+
+    /// Show USER's own fortune of the day
+    #[arg(long, requires = "today")]
+    user: Option<String>,
+
+    /// Show the fortune with the identifier ID, e.g. `startrek/12`, as shown by --verbose
+    #[arg(long, conflicts_with = "today")]
+    id: Option<String>,
+
+    /// Show the next fortune from FILE, in order, e.g. `startrek`. FILE must have been
+    /// indexed with --ordered
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["today", "id"])]
+    next: Option<String>,
+
+    /// Ignore fortune files that have no .dat file, instead of indexing them on the fly
+    #[arg(long)]
+    strict: bool,
+
+    /// Fail if a fortune isn't valid in its file's declared encoding, or UTF-8 if it has none,
+    /// instead of guessing the encoding and replacing what isn't valid
+    #[arg(long)]
+    strict_encoding: bool,
+
+    /// Check that the .dat files match their text files, and show each problem found as a
+    /// line of JSON
+    #[arg(long)]
+    check: bool,
 }
 
 impl Options {
-    pub fn folder(&self) -> Option<PathBuf> {
-        if let Some(file) = &self.folder {
-            Some(PathBuf::from(file))
+    /// Returns the fortune folders, in order of precedence: the `--folder` options, the folders
+    /// in `FORTUNE_PATH`, or the default folder.
+    pub fn folders(&self) -> Vec<PathBuf> {
+        let folders: Vec<PathBuf> = self
+            .folder
+            .iter()
+            .cloned()
+            .chain(self.folder_argument())
+            .collect();
+        if !folders.is_empty() {
+            folders
+        } else if let Some(folders) = shared_library::fortune_path() {
+            folders
         } else {
             directories::ProjectDirs::from("home", "psobolik", "fortune")
                 .map(|project_dirs| PathBuf::from(project_dirs.data_dir()))
+                .into_iter()
+                .collect()
         }
     }
+    pub fn check(&self) -> bool {
+        self.check
+    }
     pub fn summary(&self) -> bool {
         self.summary
     }
     pub fn verbose(&self) -> bool {
         self.verbose
     }
+    pub fn hide_attribution(&self) -> bool {
+        self.hide_attribution
+    }
+    /// Returns how to lay out the fortunes, or None if they are shown as they are.
+    pub fn format_options(&self) -> Option<FormatOptions> {
+        let width = match self.width {
+            Some(0) => return None,
+            Some(width) => width,
+            None => terminal_size::terminal_size()?.0 .0 as usize,
+        };
+        Some(FormatOptions::new(Some(width)))
+    }
+    pub fn fortune_options(&self) -> Result<FortuneOptions> {
+        let mut fortune_options = FortuneOptions::default();
+        if self.equal {
+            fortune_options.set_selection(Selection::Equal);
+        }
+        fortune_options.set_collection(self.collection());
+        if self.short {
+            fortune_options.set_max_length(Some(self.length));
+        }
+        if self.long {
+            fortune_options.set_min_length(Some(self.length + 1));
+        }
+        fortune_options.set_seed(self.seed).set_strict(self.strict);
+        if self.strict_encoding {
+            fortune_options.set_decoding(Decoding::Strict);
+        }
+        fortune_options.set_sources(Source::parse(self.sources())?);
+        Ok(fortune_options)
+    }
+    pub fn id(&self) -> Option<String> {
+        self.id.to_owned()
+    }
+    pub fn next(&self) -> Option<String> {
+        self.next.to_owned()
+    }
+    /// Returns the state file that keeps the user's place in the files read with --next.
+    pub fn cursors(&self) -> Option<Cursors> {
+        directories::ProjectDirs::from("home", "psobolik", "fortune").map(|project_dirs| {
+            let folder = project_dirs
+                .state_dir()
+                .unwrap_or(project_dirs.data_local_dir());
+            Cursors::new(folder.join(CURSORS_FILE))
+        })
+    }
+    pub fn today(&self) -> bool {
+        self.today
+    }
+    pub fn user(&self) -> Option<String> {
+        self.user.to_owned()
+    }
+    pub fn search(&self) -> bool {
+        self.pattern.is_some() || self.query.is_some() || self.author.is_some()
+    }
+    /// Returns the options for the search, if a pattern, a query or an author was given.
+    pub fn search_options(&self) -> Result<Option<SearchOptions>> {
+        if !self.search() {
+            return Ok(None);
+        }
+        let mut search_options = SearchOptions::new(self.pattern.as_deref().unwrap_or_default());
+        search_options
+            .set_case_insensitive(self.ignore_case)
+            .set_query(self.query.as_deref().map(Query::parse).transpose()?)
+            .set_author(self.author.to_owned())
+            .set_collection(self.collection())
+            .set_files(
+                self.fortune_options()?
+                    .sources()
+                    .iter()
+                    .map(Source::name)
+                    .collect(),
+            );
+        Ok(Some(search_options))
+    }
+    /// Returns the first positional argument if it is the path of an existing folder, i.e. it
+    /// is absolute or starts with `.` or `..`. The data folder used to be the only positional
+    /// argument, so `fortune /path/to/data` still works like `-d`, but `fortune humor` is
+    /// always the category, even if there is a `humor` folder in the current folder.
+    fn folder_argument(&self) -> Option<PathBuf> {
+        self.sources.first().map(PathBuf::from).filter(|path| {
+            (path.is_absolute() || path.starts_with(".") || path.starts_with("..")) && path.is_dir()
+        })
+    }
+    /// Returns the positional arguments that name fortune files and categories.
+    fn sources(&self) -> &[String] {
+        match self.folder_argument() {
+            Some(_) => &self.sources[1..],
+            None => &self.sources,
+        }
+    }
+    fn collection(&self) -> Collection {
+        if self.all {
+            Collection::All
+        } else if self.offensive {
+            Collection::Offensive
+        } else {
+            Collection::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_argument_is_used_as_a_folder() {
+        let folder = std::env::temp_dir();
+        let folder_name = folder.to_str().unwrap();
+        let options = Options::parse_from(["fortune", folder_name, "30%", "zippy"]);
+        assert_eq!(options.folders(), vec![folder.clone()]);
+        assert_eq!(options.sources(), ["30%", "zippy"]);
+
+        let options = Options::parse_from(["fortune", "-d", "data", folder_name]);
+        assert_eq!(options.folders(), [PathBuf::from("data"), folder]);
+        assert!(options.sources().is_empty());
+
+        let options = Options::parse_from(["fortune", "./", "startrek"]);
+        assert_eq!(options.folders(), [PathBuf::from("./")]);
+        assert_eq!(options.sources(), ["startrek"]);
+
+        // A name that isn't the path of a folder is a fortune file or a category
+        for name in ["startrek", "src", "./startrek"] {
+            let options = Options::parse_from(["fortune", "-d", "data", name]);
+            assert_eq!(options.folders(), [PathBuf::from("data")]);
+            assert_eq!(options.sources(), [name]);
+        }
+    }
 }
@@ -9,10 +9,15 @@ use std::process::ExitCode;
 use clap::Parser;
 use tokio::fs;
 use tokio::io;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 
 use options::Options;
-use shared_library::{DataFile, Flags, Header};
+use shared_library::compression::read_text;
+use shared_library::text_encoding::{declared_encoding, decode, encoding_for_label, encoding_path};
+use shared_library::word_index::word_index_path;
+use shared_library::{
+    rot13, validate_data_file, DataFile, Decoding, Encoding, FortuneError, Result, WordIndex,
+};
 
 mod options;
 
@@ -22,103 +27,204 @@ mod options;
 async fn main() -> ExitCode {
     // This app will set the Random, Ordered or Rotated flags in the output file,
     // but otherwise the file will be the same. If you want the data in some particular
-    // order or rotated, do it in the input file.
-    // TIP: Use this command to ROT13 the file:
-    // $ cat scratch | tr '[a-z][A-Z]' '[n-za-m][N-ZA-M]' >scratch.rot
+    // order, do it in the input file. Use --encode to write a ROT13 encoded copy of it.
 
     let options = Options::parse();
+    if options.check() {
+        return check(&options.dat_file(), &options.file()).await;
+    }
 
-    match process(
-        options.file(),
-        options.dat_file(),
-        Some(options.separator()),
-        Some(options.flags()),
-        options.quiet(),
-    )
-    .await
-    {
+    let separator = match options.separator() {
+        Ok(separator) => separator,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    let encoding = match options.encoding() {
+        Some(label) => match encoding_for_label(&label) {
+            Some(encoding) => Some(encoding),
+            None => {
+                eprintln!("Error: Unknown or unsupported encoding '{}'", label);
+                return ExitCode::from(2);
+            }
+        },
+        None => None,
+    };
+    // Index the fortune file and check its encoding before anything is written, so a run that
+    // fails with --strict leaves the files from the last run alone
+    let data_file =
+        match DataFile::build(options.file(), Some(separator), Some(options.flags())).await {
+            Ok(data_file) => data_file,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return ExitCode::from(1);
+            }
+        };
+    let text_file = options.encode().unwrap_or(options.file());
+    let encoding = match encoding {
+        Some(encoding) => Some(encoding),
+        None if options.no_encoding() => None,
+        None => match declared_encoding(&text_file).await {
+            Ok(encoding) => encoding,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return ExitCode::from(1);
+            }
+        },
+    };
+    match check_encoding(&options.file(), &data_file, encoding).await {
+        Ok(invalid) if invalid.is_empty() => {}
+        Ok(invalid) => {
+            let indexes: Vec<String> = invalid.iter().map(usize::to_string).collect();
+            let problem = format!(
+                "{} {} {} not valid {}",
+                if invalid.len() == 1 {
+                    "Fortune"
+                } else {
+                    "Fortunes"
+                },
+                indexes.join(", "),
+                if invalid.len() == 1 { "is" } else { "are" },
+                encoding.map_or("UTF-8", Encoding::name)
+            );
+            if options.strict() {
+                eprintln!("Error: {}; nothing was written", problem);
+                return ExitCode::from(3);
+            }
+            // This is what the library does with them
+            let fallback = match encoding {
+                Some(_) => "invalid text will be replaced",
+                None => "the text will be read as Windows-1252",
+            };
+            eprintln!("Warning: {}; {}", problem, fallback);
+        }
         Err(error) => {
             eprintln!("Error: {}", error);
-            ExitCode::from(1)
+            return ExitCode::from(1);
+        }
+    }
+
+    if let Some(encoded_file) = options.encode() {
+        if let Err(error) = encode_file(&options.file(), &encoded_file).await {
+            eprintln!("Error: {}", error);
+            return ExitCode::from(1);
+        }
+        if !options.quiet() {
+            println!("Encoded file:    {}", encoded_file.display());
         }
-        _ => ExitCode::default(),
     }
+    if let Err(error) = write_data_file(&data_file, &options.dat_file()).await {
+        eprintln!("Error: {}", error);
+        return ExitCode::from(1);
+    }
+    if !options.quiet() {
+        show_summary(&data_file, &options.file(), &options.dat_file());
+    }
+    if let Err(error) = declare_encoding(&text_file, encoding, options.no_encoding()).await {
+        eprintln!("Error: {}", error);
+        return ExitCode::from(1);
+    }
+    if options.words() {
+        let word_index_file = word_index_path(&options.dat_file());
+        if let Err(error) =
+            write_word_index(&text_file, &word_index_file, &data_file, encoding).await
+        {
+            eprintln!("Error: {}", error);
+            return ExitCode::from(1);
+        }
+        if !options.quiet() {
+            println!("Word index:      {}", word_index_file.display());
+        }
+    }
+    ExitCode::default()
 }
 
-async fn process<P: AsRef<Path>>(
-    in_file: P,
-    out_file: P,
-    separator: Option<char>,
-    flags: Option<Flags>,
-    quiet: bool,
-) -> io::Result<()> {
-    let data_file = build_data_file(&in_file, separator, flags).await?;
-    write_data_file(&data_file, &out_file).await?;
-    if !quiet {
-        show_summary(&data_file, &in_file, &out_file)
+/// Checks the .dat file against the fortune file, and shows each problem found as a line of
+/// JSON. Exits with code 9 if there are any, as `fortune --check` does.
+async fn check(dat_file: &Path, text_file: &Path) -> ExitCode {
+    let issues = validate_data_file(dat_file, text_file).await;
+    for issue in &issues {
+        println!("{}", serde_json::to_string(issue).unwrap_or_default());
+    }
+    if issues.is_empty() {
+        ExitCode::default()
+    } else {
+        ExitCode::from(9)
+    }
+}
+/// Declares the given encoding of the fortune text file that the library will read, or
+/// removes the declaration if `undeclare` is true. Otherwise, any existing declaration is left
+/// alone.
+async fn declare_encoding(
+    text_file: &Path,
+    encoding: Option<&'static Encoding>,
+    undeclare: bool,
+) -> Result<()> {
+    let declaration = encoding_path(text_file);
+    let io_error = |error| FortuneError::Io {
+        path: declaration.to_owned(),
+        source: error,
     };
-    Ok(())
+    match encoding {
+        Some(encoding) => fs::write(&declaration, encoding.name())
+            .await
+            .map_err(io_error),
+        None if undeclare => match fs::remove_file(&declaration).await {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(io_error(error)),
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+/// Returns the indexes of the fortunes in the original file that aren't valid in the encoding.
+async fn check_encoding(
+    in_file: &Path,
+    data_file: &DataFile,
+    encoding: Option<&'static Encoding>,
+) -> io::Result<Vec<usize>> {
+    let text = read_text(in_file).await?;
+    let invalid = (0..data_file.header.count() as usize)
+        .filter(|index| {
+            data_file
+                .fortune_range(*index)
+                .and_then(|range| text.get(range))
+                .and_then(|bytes| decode(bytes, encoding, Decoding::Strict))
+                .is_none()
+        })
+        .collect();
+    Ok(invalid)
 }
 fn show_summary<P: AsRef<Path>>(data_file: &DataFile, in_file: P, out_file: P) {
     println!("Processed file:  {}", in_file.as_ref().display());
     println!("Generated file:  {}", out_file.as_ref().display());
     println!("Number of items: {}", data_file.header.count());
+    println!("Separator:       {}", data_file.separator);
     println!("Flags:           [{}]", data_file.header.flags());
     println!("Shortest:        {}", data_file.header.shortest());
     println!("Longest:         {}", data_file.header.longest());
 }
+/// Writes a ROT13 encoded copy of the fortune file, decompressed if it is compressed. The
+/// encoding doesn't change the length of the text, so the offsets of the original file are
+/// still valid.
+async fn encode_file<P: AsRef<Path>>(in_file: P, out_file: P) -> io::Result<()> {
+    let mut bytes = read_text(in_file.as_ref()).await?;
+    rot13(&mut bytes);
+    fs::write(out_file, bytes).await
+}
+/// Writes the word index of the fortune file, which the .dat file indexes.
+async fn write_word_index(
+    text_file: &Path,
+    word_index_file: &Path,
+    data_file: &DataFile,
+    encoding: Option<&'static Encoding>,
+) -> io::Result<()> {
+    let text = read_text(text_file).await?;
+    let word_index = WordIndex::build(&text, data_file, encoding);
+    fs::write(word_index_file, word_index.to_bytes()).await
+}
 async fn write_data_file<P: AsRef<Path>>(data_file: &DataFile, out_file: P) -> io::Result<()> {
     let mut file = fs::File::create(out_file).await?;
     file.write_all(data_file.to_bytes().as_ref()).await?;
     Ok(())
 }
-
-async fn build_data_file<P: AsRef<Path>>(
-    in_file: P,
-    separator: Option<char>,
-    flags: Option<Flags>,
-) -> io::Result<DataFile> {
-    let separator = separator.unwrap_or('%');
-    let flags = flags.unwrap_or_default();
-    let end_line = format!("{separator}\n");
-
-    let mut count = u32::MIN;
-    let mut shortest = u32::MAX;
-    let mut longest = u32::MIN;
-    let mut offsets: Vec<u32> = vec![];
-
-    let in_file = fs::File::open(in_file).await?;
-    let mut reader = io::BufReader::new(in_file);
-
-    let mut line = String::new();
-    let mut len = 0;
-    let mut offset = 0;
-
-    while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
-        let line_len = line.len() as u32;
-        if line != end_line {
-            len += line_len;
-        } else {
-            // End of the entry
-            if len < shortest {
-                shortest = len;
-            } else if len > longest {
-                longest = len;
-            }
-            offsets.push(offset);
-            offset += len + line_len;
-            count += 1;
-            len = 0;
-        }
-        line.clear();
-    }
-    offsets.push(offset); // End of file offset
-    let header = Header::default()
-        .set_separator(separator)
-        .set_count(count)
-        .set_longest(longest)
-        .set_shortest(shortest)
-        .set_flags(flags)
-        .to_owned();
-    Ok(DataFile { header, offsets })
-}
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use shared_library::Flags;
+use shared_library::{Flags, Result, Separator};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -21,9 +21,20 @@ pub struct Options {
     #[arg(short, long)]
     quiet: bool,
 
-    /// The separator character used in the fortune file
-    #[arg(short, long, default_value = "%")]
-    separator: char,
+    /// The text of the separator lines in the fortune file, e.g. %% or ----
+    #[arg(short, long, default_value = "%", allow_hyphen_values = true)]
+    separator: String,
+
+    /// A regular expression that the separator lines in the fortune file match as a whole,
+    /// e.g. '-{3,}', instead of a separator
+    #[arg(
+        short,
+        long,
+        value_name = "REGEX",
+        allow_hyphen_values = true,
+        conflicts_with = "separator"
+    )]
+    pattern: Option<String>,
 
     /// Set the Random flag
     #[arg(long)]
@@ -36,6 +47,34 @@ pub struct Options {
     /// Set the Rotated flag
     #[arg(long)]
     rotated: bool,
+
+    /// ROT13 encode the fortune file, write it to ENCODED_FILE, and set the Rotated flag.
+    /// The .dat file is named <ENCODED_FILE>.dat by default
+    #[arg(short, long, value_name = "ENCODED_FILE")]
+    encode: Option<PathBuf>,
+
+    /// The character encoding of the fortune file, e.g. latin1, which is declared in a
+    /// .encoding file next to it [default: the encoding already declared, or UTF-8]
+    #[arg(long, value_name = "LABEL")]
+    encoding: Option<String>,
+
+    /// Remove the fortune file's .encoding file, so it is read as UTF-8
+    #[arg(long, conflicts_with = "encoding")]
+    no_encoding: bool,
+
+    /// Fail if a fortune isn't valid in the encoding, instead of warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Also build a word index of the fortunes, named for the .dat file with the extension
+    /// .idx, which makes word queries faster
+    #[arg(short, long)]
+    words: bool,
+
+    /// Check that the .dat file matches the fortune file instead of generating it, show each
+    /// problem found as a line of JSON, and exit with code 9 if there are any
+    #[arg(long, conflicts_with_all = ["encode", "encoding", "no_encoding", "strict", "words"])]
+    check: bool,
 }
 
 #[allow(dead_code)]
@@ -46,17 +85,43 @@ impl Options {
     pub fn dat_file(&self) -> PathBuf {
         if let Some(dat_file) = &self.dat_file {
             PathBuf::from(dat_file)
+        } else if let Some(encoded_file) = &self.encode {
+            // The .dat file pairs with the text file named by removing its extension, so it
+            // has to be named for all of ENCODED_FILE, not for the plain text next to it
+            let mut dat_file = encoded_file.clone().into_os_string();
+            dat_file.push(".dat");
+            PathBuf::from(dat_file)
         } else {
-            PathBuf::from(&self.file)
-                .with_extension("")
-                .with_extension("dat")
+            self.file.with_extension("").with_extension("dat")
         }
     }
+    pub fn encode(&self) -> Option<PathBuf> {
+        self.encode.as_ref().map(PathBuf::from)
+    }
+    pub fn encoding(&self) -> Option<String> {
+        self.encoding.to_owned()
+    }
+    pub fn no_encoding(&self) -> bool {
+        self.no_encoding
+    }
+    pub fn check(&self) -> bool {
+        self.check
+    }
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+    pub fn words(&self) -> bool {
+        self.words
+    }
     pub fn quiet(&self) -> bool {
         self.quiet
     }
-    pub fn separator(&self) -> char {
-        self.separator
+    /// Returns the separator, which fails if the pattern isn't a valid regular expression.
+    pub fn separator(&self) -> Result<Separator> {
+        match &self.pattern {
+            Some(pattern) => Separator::pattern(pattern),
+            None => Ok(Separator::Line(self.separator.to_owned())),
+        }
     }
     pub fn flags(&self) -> Flags {
         let mut flags = Flags::empty();
@@ -66,9 +131,33 @@ impl Options {
         if self.ordered {
             flags |= Flags::Ordered;
         }
-        if self.rotated {
+        if self.rotated || self.encode.is_some() {
             flags |= Flags::Rotated;
         }
         flags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dat_file_is_named_for_the_text_file() {
+        let options = Options::parse_from(["data_file_gen", "off/limerick"]);
+        assert_eq!(options.dat_file(), PathBuf::from("off/limerick.dat"));
+        let options = Options::parse_from(["data_file_gen", "startrek.gz"]);
+        assert_eq!(options.dat_file(), PathBuf::from("startrek.dat"));
+        let options = Options::parse_from(["data_file_gen", "zippy", "yow.dat"]);
+        assert_eq!(options.dat_file(), PathBuf::from("yow.dat"));
+    }
+    #[test]
+    fn encoded_dat_file_pairs_with_the_encoded_file() {
+        let options =
+            Options::parse_from(["data_file_gen", "-e", "off/limerick.rot", "off/limerick"]);
+        let dat_file = options.dat_file();
+        assert_eq!(dat_file, PathBuf::from("off/limerick.rot.dat"));
+        // The library finds a .dat file's text file by removing its extension
+        assert_eq!(dat_file.with_extension(""), options.encode().unwrap());
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::env;
+use std::fs::File;
+use std::path::PathBuf;
+
+/// The environment variable that names the data folder to build into the library.
+const EMBED_DIR: &str = "FORTUNE_EMBED_DIR";
+
+/// With the `embedded` feature, archives the data folder named by `FORTUNE_EMBED_DIR`, so the
+/// library can include it. A relative path is relative to the workspace root, not to this
+/// crate's folder, where build scripts run. If the variable isn't set, the archive is empty,
+/// so the feature still builds, e.g. with `--all-features`, but has no fortunes.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBEDDED").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed={EMBED_DIR}");
+    let data_path = env::var_os(EMBED_DIR).map(|data_path| workspace_root().join(data_path));
+    match &data_path {
+        Some(data_path) if !data_path.is_dir() => panic!(
+            "{EMBED_DIR} names '{}', which isn't a folder",
+            data_path.display()
+        ),
+        Some(data_path) => println!("cargo:rerun-if-changed={}", data_path.display()),
+        None => println!(
+            "cargo:warning={EMBED_DIR} isn't set, so no fortunes are embedded; set it to the data \
+             folder to embed"
+        ),
+    }
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("Cargo sets OUT_DIR"));
+    let archive = File::create(out_dir.join("embedded.tar")).expect("Cannot create the archive");
+    let mut builder = tar::Builder::new(archive);
+    builder.follow_symlinks(true);
+    if let Some(data_path) = &data_path {
+        builder
+            .append_dir_all(".", data_path)
+            .expect("Cannot archive the data folder");
+    }
+    builder.finish().expect("Cannot archive the data folder");
+}
+
+/// Returns the workspace root, which is the folder this crate is in.
+fn workspace_root() -> PathBuf {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("Cargo sets it"));
+    manifest_dir
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or(manifest_dir)
+}
@@ -0,0 +1,165 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+use ruzstd::StreamingDecoder;
+use tokio::fs;
+
+/// The ways a fortune text file can be compressed. A compressed text file is named for the
+/// fortune file, with the compression's extension, e.g. `startrek.gz`, and its `.dat` file
+/// indexes the text once it is decompressed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+impl Compression {
+    pub const ALL: [Compression; 2] = [Compression::Gzip, Compression::Zstd];
+
+    /// The extension of a text file compressed this way, e.g. `gz`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+    /// Returns the compression that the path's extension names, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?;
+        Self::ALL
+            .into_iter()
+            .find(|compression| extension == compression.extension())
+    }
+    pub fn decompress(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        let mut text = vec![];
+        match self {
+            Compression::Gzip => MultiGzDecoder::new(bytes).read_to_end(&mut text)?,
+            Compression::Zstd => StreamingDecoder::new(bytes)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+                .read_to_end(&mut text)?,
+        };
+        Ok(text)
+    }
+}
+
+/// Returns the path of the given text file, compressed the given way.
+pub fn compressed_path(text_path: &Path, compression: Compression) -> PathBuf {
+    text_path.with_extension(compression.extension())
+}
+
+/// Returns the way the given text file is compressed, if there is a compressed text file
+/// instead of it.
+pub fn find_compression(text_path: &Path) -> Option<Compression> {
+    if text_path.exists() {
+        return None;
+    }
+    Compression::ALL
+        .into_iter()
+        .find(|compression| compressed_path(text_path, *compression).is_file())
+}
+
+/// Reads a text file, decompressing it if its extension names a compression.
+pub async fn read_text(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path).await?;
+    match Compression::from_path(path) {
+        Some(compression) => compression.decompress(&bytes),
+        None => Ok(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use flate2::write::GzEncoder;
+
+    use super::*;
+    use crate::{Corpus, FortuneError, FortuneOptions, MemorySource};
+
+    const TEXT: &[u8] = b"Kirk\n%\nSpock\n%\nMcCoy\n%\n";
+
+    fn gzip(text: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(text).unwrap();
+        encoder.finish().unwrap()
+    }
+    /// Returns a zstd frame that stores the text, of less than 256 bytes, in one raw block,
+    /// since `ruzstd` can only decompress.
+    fn zstd(text: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x28, 0xb5, 0x2f, 0xfd];
+        // A single segment, with a one byte content size
+        frame.extend([0x20, text.len() as u8]);
+        // The last block, which is raw
+        let block_header = 1 | (text.len() as u32) << 3;
+        frame.extend(&block_header.to_le_bytes()[..3]);
+        frame.extend(text);
+        frame
+    }
+
+    #[test]
+    fn decompress_round_trips() {
+        assert_eq!(Compression::Gzip.decompress(&gzip(TEXT)).unwrap(), TEXT);
+        assert_eq!(Compression::Zstd.decompress(&zstd(TEXT)).unwrap(), TEXT);
+    }
+    #[test]
+    fn decompress_rejects_truncated_and_corrupt_streams() {
+        let gzipped = gzip(TEXT);
+        assert!(Compression::Gzip
+            .decompress(&gzipped[..gzipped.len() / 2])
+            .is_err());
+        assert!(Compression::Gzip.decompress(TEXT).is_err());
+        let zstd = zstd(TEXT);
+        assert!(Compression::Zstd
+            .decompress(&zstd[..zstd.len() - 4])
+            .is_err());
+        assert!(Compression::Zstd.decompress(&zstd[..3]).is_err());
+        assert!(Compression::Zstd.decompress(TEXT).is_err());
+    }
+    #[tokio::test]
+    async fn read_text_decompresses_by_extension() {
+        let folder =
+            std::env::temp_dir().join(format!("fortune-compression-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let text_path = folder.join("startrek");
+        assert_eq!(find_compression(&text_path), None);
+        for (compression, bytes) in [
+            (Compression::Gzip, gzip(TEXT)),
+            (Compression::Zstd, zstd(TEXT)),
+        ] {
+            let path = compressed_path(&text_path, compression);
+            std::fs::write(&path, bytes).unwrap();
+            assert_eq!(Compression::from_path(&path), Some(compression));
+            assert_eq!(find_compression(&text_path), Some(compression));
+            assert_eq!(read_text(&path).await.unwrap(), TEXT);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+    #[tokio::test]
+    async fn compressed_fortune_files_are_read() {
+        let mut source = MemorySource::new();
+        source
+            .insert(Path::new("startrek.gz"), gzip(TEXT))
+            .insert(Path::new("humor/puns.zst"), zstd(b"A short pun\n%\n"));
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        let fortune = corpus.fortune_by_id("startrek/2").await.unwrap();
+        assert_eq!(fortune.fortune(), "McCoy\n");
+        let fortune = corpus.fortune_by_id("humor/puns/0").await.unwrap();
+        assert_eq!(fortune.fortune(), "A short pun\n");
+    }
+    #[tokio::test]
+    async fn corrupt_fortune_files_fail_to_load() {
+        let gzipped = gzip(TEXT);
+        let mut source = MemorySource::new();
+        source.insert(Path::new("startrek.gz"), &gzipped[..gzipped.len() - 8]);
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default()).await;
+        assert!(matches!(corpus, Err(FortuneError::Io { .. })));
+    }
+}
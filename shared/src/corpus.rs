@@ -0,0 +1,691 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::{Local, NaiveDate};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    choose_by_weight, choose_equal, choose_weighted, read_fortune, source_weights, Candidates,
+    Collection, Cursors, Decoding, DirectorySource, Fortune, FortuneError, FortuneFileInfo,
+    FortuneOptions, FortuneSource, FortuneStats, Result, Search, SearchOptions, Selection,
+};
+
+/// All the fortune files in a [FortuneSource], such as one or more data folders, loaded once
+/// so that fortunes can be chosen without reading the `.dat` files again.
+pub struct Corpus {
+    source: Arc<dyn FortuneSource>,
+    fortune_files: Vec<FortuneFileInfo>,
+    decoding: Decoding,
+    fingerprint: u64,
+}
+impl Corpus {
+    /// Loads the fortune files in the given path, both offensive and not.
+    pub async fn load(data_path: &PathBuf) -> Result<Self> {
+        Self::load_with(data_path, &FortuneOptions::default()).await
+    }
+    /// Loads the fortune files in the given path, both offensive and not. If the options are
+    /// strict, text files without a `.dat` file are ignored instead of being indexed in memory.
+    pub async fn load_with(data_path: &PathBuf, options: &FortuneOptions) -> Result<Self> {
+        Self::load_paths(std::slice::from_ref(data_path), options).await
+    }
+    /// Loads the fortune files in the given paths, in order, as one corpus. Paths that don't
+    /// exist are skipped. If more than one path has a fortune file with the same identifier,
+    /// e.g. `startrek` or `humor/puns`, the one in the earliest path is used. The options'
+    /// [Decoding] is used whenever a fortune is read.
+    pub async fn load_paths(data_paths: &[PathBuf], options: &FortuneOptions) -> Result<Self> {
+        Self::load_source(
+            Arc::new(DirectorySource::new(data_paths.to_vec())?),
+            options,
+        )
+        .await
+    }
+    /// Loads the fortune files in the given source, both offensive and not.
+    pub async fn load_source(
+        source: Arc<dyn FortuneSource>,
+        options: &FortuneOptions,
+    ) -> Result<Self> {
+        // Take the fingerprint first, so a change made while loading makes the corpus stale
+        let fingerprint = source.fingerprint().await;
+        let fortune_files = source.fortune_files(options).await?;
+        Ok(Self {
+            source,
+            fortune_files,
+            decoding: options.decoding(),
+            fingerprint,
+        })
+    }
+    pub fn source(&self) -> &Arc<dyn FortuneSource> {
+        &self.source
+    }
+    /// Returns the source's path, which is the one reported in errors.
+    pub fn data_path(&self) -> &PathBuf {
+        self.source.path()
+    }
+    /// Returns the fortune files that are in the given collection.
+    pub fn fortune_files(&self, collection: Collection) -> Vec<&FortuneFileInfo> {
+        self.fortune_files
+            .iter()
+            .filter(|fortune_file_info| {
+                if fortune_file_info.is_offensive() {
+                    collection.includes_offensive()
+                } else {
+                    collection.includes_normal()
+                }
+            })
+            .collect()
+    }
+    /// Returns a fortune chosen randomly, as directed by the given [FortuneOptions].
+    /// If the options have a seed, the same seed always chooses the same fortune.
+    pub async fn random_fortune(&self, options: &FortuneOptions) -> Result<Fortune> {
+        let (fortune_file_info, index) = match options.seed() {
+            Some(seed) => self.choose(options, &mut seeded_rng(seed))?,
+            None => self.choose(options, &mut rand::thread_rng())?,
+        };
+        // Return the text between the offsets in the fortune file.
+        read_fortune(
+            self.source.as_ref(),
+            fortune_file_info,
+            index,
+            self.decoding,
+        )
+        .await
+    }
+    /// Returns a fortune chosen with the given random number generator, as directed by the
+    /// given [FortuneOptions].
+    pub async fn random_fortune_with_rng<R: Rng + Send>(
+        &self,
+        options: &FortuneOptions,
+        rng: &mut R,
+    ) -> Result<Fortune> {
+        let (fortune_file_info, index) = self.choose(options, rng)?;
+        read_fortune(
+            self.source.as_ref(),
+            fortune_file_info,
+            index,
+            self.decoding,
+        )
+        .await
+    }
+    /// Returns the fortune with the given identifier, e.g. `startrek/12`.
+    pub async fn fortune_by_id(&self, id: &str) -> Result<Fortune> {
+        let (fortune_file_info, index) = self.find_id(id)?;
+        read_fortune(
+            self.source.as_ref(),
+            fortune_file_info,
+            index,
+            self.decoding,
+        )
+        .await
+    }
+    /// Returns the identifiers of the fortunes before and after the one with the given
+    /// identifier in its file, if there are any.
+    pub fn adjacent_ids(&self, id: &str) -> Result<(Option<String>, Option<String>)> {
+        let (fortune_file_info, index) = self.find_id(id)?;
+        let previous = index
+            .checked_sub(1)
+            .map(|index| fortune_file_info.fortune_id(index));
+        let next = Some(index + 1)
+            .filter(|index| *index < fortune_file_info.count())
+            .map(|index| fortune_file_info.fortune_id(index));
+        Ok((previous, next))
+    }
+    /// Returns the fortune file and index that the given identifier refers to.
+    fn find_id(&self, id: &str) -> Result<(&FortuneFileInfo, usize)> {
+        let invalid = || FortuneError::InvalidId { id: id.to_owned() };
+        let (collection_id, index) = id.trim_matches('/').rsplit_once('/').ok_or_else(invalid)?;
+        let index = index.parse::<usize>().map_err(|_| invalid())?;
+        self.fortune_files
+            .iter()
+            .find(|fortune_file_info| fortune_file_info.collection_id() == collection_id)
+            .filter(|fortune_file_info| index < fortune_file_info.count())
+            .map(|fortune_file_info| (fortune_file_info, index))
+            .ok_or_else(|| FortuneError::NoSuchFortune {
+                path: self.data_path().to_owned(),
+                id: id.to_owned(),
+            })
+    }
+    /// Returns the next fortune, in index order, from the fortune file with the given
+    /// identifier, e.g. `startrek` or `off/limerick`. Each reader has its own place in
+    /// each file, which is kept in the given [Cursors]. After the last fortune, it starts
+    /// over. The file's `.dat` file must have the Ordered flag.
+    pub async fn next_fortune(&self, id: &str, cursors: &Cursors, reader: &str) -> Result<Fortune> {
+        let id = id.trim_matches('/');
+        let fortune_file_info = self
+            .fortune_files
+            .iter()
+            .find(|fortune_file_info| fortune_file_info.collection_id() == id)
+            .ok_or_else(|| FortuneError::UnknownSource {
+                path: self.data_path().to_owned(),
+                name: id.to_owned(),
+            })?;
+        if !fortune_file_info.data_file.header.is_ordered() {
+            return Err(FortuneError::NotOrdered {
+                path: fortune_file_info.path().to_owned(),
+                name: id.to_owned(),
+            });
+        }
+        if fortune_file_info.count() == 0 {
+            return Err(FortuneError::EmptyCorpus {
+                path: fortune_file_info.path().to_owned(),
+            });
+        }
+        let index = cursors
+            .advance(reader, id, fortune_file_info.count())
+            .await?;
+        read_fortune(
+            self.source.as_ref(),
+            fortune_file_info,
+            index,
+            self.decoding,
+        )
+        .await
+    }
+    /// Returns the fortune of the day for the given date, or today if there isn't one.
+    /// Everyone gets the same fortune on the same day, unless a user name is given,
+    /// in which case each user gets their own. The choice only changes if the fortune
+    /// files do.
+    pub async fn fortune_of_the_day(
+        &self,
+        options: &FortuneOptions,
+        date: Option<NaiveDate>,
+        user: Option<&str>,
+    ) -> Result<Fortune> {
+        let date = date.unwrap_or_else(|| Local::now().date_naive());
+        let mut key = date.format("%Y-%m-%d").to_string();
+        if let Some(user) = user {
+            key.push('\0');
+            key.push_str(user);
+        }
+        let mut rng = seeded_rng(fnv1a(key.as_bytes()));
+        self.random_fortune_with_rng(options, &mut rng).await
+    }
+    /// Chooses a fortune file and the index of a fortune in it, as directed by the given
+    /// [FortuneOptions].
+    pub fn choose<R: Rng + ?Sized>(
+        &self,
+        options: &FortuneOptions,
+        rng: &mut R,
+    ) -> Result<(&FortuneFileInfo, usize)> {
+        let candidates: Vec<Candidates> = self
+            .fortune_files(options.collection())
+            .into_iter()
+            .map(|fortune_file_info| Candidates::new(fortune_file_info, options))
+            .collect();
+        // Files with weights in their manifests need the weighted choice
+        let weighted = candidates
+            .iter()
+            .any(|candidates| candidates.fortune_file_info.weight() != 1.0);
+        let choice = if !options.sources().is_empty() || weighted {
+            let weights = source_weights(self.data_path(), &candidates, options)?;
+            choose_by_weight(&candidates, &weights, rng)
+        } else {
+            match options.selection() {
+                Selection::Weighted => choose_weighted(&candidates, rng),
+                Selection::Equal => choose_equal(&candidates, rng),
+            }
+        };
+        choice.ok_or_else(|| FortuneError::EmptyCorpus {
+            path: self.data_path().to_owned(),
+        })
+    }
+    /// Returns an array of [FortuneStats], with an entry for each of the fortune files
+    /// in the collection given by the [FortuneOptions]. If the options have sources, only
+    /// the files they name, or the files in the categories they name, are included. Each
+    /// entry has the percent chance of a fortune being chosen from the file, given the
+    /// options' sources, lengths and selection, and the files' weights.
+    pub fn fortune_stats(&self, options: &FortuneOptions) -> Result<Vec<FortuneStats>> {
+        let candidates: Vec<Candidates> = self
+            .fortune_files(options.collection())
+            .into_iter()
+            .map(|fortune_file_info| Candidates::new(fortune_file_info, options))
+            .collect();
+        let weights = source_weights(self.data_path(), &candidates, options)?;
+        let total = weights.iter().sum::<f64>();
+        let fortune_stats = candidates
+            .iter()
+            .zip(weights)
+            .filter(|(candidates, _)| {
+                options.sources().is_empty()
+                    || options
+                        .sources()
+                        .iter()
+                        .any(|source| candidates.fortune_file_info.matches(&source.name()))
+            })
+            .map(|(candidates, weight)| {
+                let fortune_file_info = candidates.fortune_file_info;
+                let mut fortune_stats = FortuneStats::new(
+                    fortune_file_info.fortune_file(),
+                    fortune_file_info.category(),
+                    fortune_file_info.count(),
+                    fortune_file_info.is_offensive(),
+                );
+                let percent = if total > 0.0 {
+                    weight * 100.0 / total
+                } else {
+                    0.0
+                };
+                fortune_stats
+                    .set_percent(percent)
+                    .set_manifest(fortune_file_info.manifest().cloned());
+                fortune_stats
+            })
+            .collect();
+        Ok(fortune_stats)
+    }
+    /// Starts a search for the fortunes that match the given [SearchOptions].
+    /// If the options name files or categories that aren't in the corpus, it's an error.
+    pub fn search(&self, options: &SearchOptions) -> Result<Search<'_>> {
+        let fortune_files = self.fortune_files(options.collection());
+        for name in options.files() {
+            if !fortune_files
+                .iter()
+                .any(|fortune_file_info| fortune_file_info.matches(name))
+            {
+                return Err(FortuneError::UnknownSource {
+                    path: self.data_path().to_owned(),
+                    name: name.to_owned(),
+                });
+            }
+        }
+        let fortune_files = fortune_files
+            .into_iter()
+            .filter(|fortune_file_info| {
+                options.files().is_empty()
+                    || options
+                        .files()
+                        .iter()
+                        .any(|name| fortune_file_info.matches(name))
+            })
+            .collect();
+        Search::new(options, self.source.as_ref(), fortune_files, self.decoding)
+    }
+    /// Returns true if any of the source's files have been added, removed or changed since
+    /// the corpus was loaded.
+    pub async fn is_stale(&self) -> bool {
+        self.source.fingerprint().await != self.fingerprint
+    }
+}
+
+/// Returns a random number generator seeded with the given seed. Unlike `StdRng`, whose
+/// algorithm may change between versions of `rand`, ChaCha8 always gives the same numbers
+/// for the same seed, so a seeded choice is the same in every build.
+fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+/// The 64-bit FNV-1a hash of the bytes. Unlike the standard library's hasher,
+/// it's the same in every build, so a fortune of the day stays the same.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    use super::*;
+    use crate::{DataFile, Flags, MemorySource, Source};
+
+    /// Fortune text files, without `.dat` files, so they are indexed as they are loaded
+    pub(crate) const FILES: &[(&str, &str)] = &[
+        ("startrek", "Kirk\n%\nSpock\n%\nMcCoy\n%\n"),
+        ("zippy", "Yow!\n%\n"),
+        (
+            "humor/puns",
+            "A short pun\n%\nA much longer pun than the other one\n%\n",
+        ),
+    ];
+
+    /// Loads a corpus from files held in memory, given their paths and contents.
+    pub(crate) async fn memory_corpus(files: &[(&str, &str)]) -> Corpus {
+        let mut source = MemorySource::new();
+        for (path, contents) in files {
+            source.insert(Path::new(path), contents.as_bytes().to_vec());
+        }
+        Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .expect("The files load")
+    }
+    /// Loads a corpus from files written to a temporary data folder with the given name, given
+    /// their paths and contents.
+    pub(crate) async fn directory_corpus(name: &str, files: &[(&str, &str)]) -> Corpus {
+        let data_path = std::env::temp_dir()
+            .join(format!("fortune-corpus-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&data_path);
+        for (path, contents) in files {
+            let path = data_path.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        Corpus::load_with(&data_path, &FortuneOptions::default())
+            .await
+            .expect("The files load")
+    }
+    /// Loads the files both from memory and from a temporary data folder with the given name,
+    /// so a test can check that every source lists and chooses them the same way.
+    pub(crate) async fn corpora(name: &str, files: &[(&str, &str)]) -> [Corpus; 2] {
+        [
+            memory_corpus(files).await,
+            directory_corpus(name, files).await,
+        ]
+    }
+    /// Returns the identifiers of the fortunes chosen with a generator seeded with the seed,
+    /// the way [Corpus::random_fortune] seeds it.
+    pub(crate) fn choices(corpus: &Corpus, options: &FortuneOptions, seed: u64) -> Vec<String> {
+        let mut rng = seeded_rng(seed);
+        (0..100)
+            .map(|_| {
+                let (fortune_file_info, index) = corpus.choose(options, &mut rng).unwrap();
+                fortune_file_info.fortune_id(index)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn same_seed_makes_same_choices() {
+        let corpus = memory_corpus(FILES).await;
+        let options = FortuneOptions::default();
+        assert_eq!(
+            choices(&corpus, &options, 42),
+            choices(&corpus, &options, 42)
+        );
+        assert_ne!(
+            choices(&corpus, &options, 42),
+            choices(&corpus, &options, 43)
+        );
+    }
+    #[tokio::test]
+    async fn seeded_choices_are_pinned() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = FortuneOptions::default();
+        let chosen = choices(&corpus, &options, 7);
+        // The first choice is the fortune that `fortune --seed 7` shows
+        options.set_seed(Some(7));
+        let fortune = corpus.random_fortune(&options).await.unwrap();
+        assert_eq!(fortune.id(), chosen[0]);
+        assert_eq!(
+            chosen[..5],
+            [
+                "humor/puns/1",
+                "startrek/2",
+                "startrek/2",
+                "startrek/1",
+                "startrek/0"
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn seeded_choices_cover_every_fortune() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = FortuneOptions::default();
+        for selection in [Selection::Weighted, Selection::Equal] {
+            options.set_selection(selection);
+            let chosen: BTreeSet<String> = choices(&corpus, &options, 1).into_iter().collect();
+            assert_eq!(
+                chosen,
+                BTreeSet::from(
+                    [
+                        "humor/puns/0",
+                        "humor/puns/1",
+                        "startrek/0",
+                        "startrek/1",
+                        "startrek/2",
+                        "zippy/0"
+                    ]
+                    .map(str::to_owned)
+                )
+            );
+        }
+    }
+    #[tokio::test]
+    async fn random_fortune_with_seed_is_pinned() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = FortuneOptions::default();
+        options.set_seed(Some(2026));
+        let fortune = corpus.random_fortune(&options).await.unwrap();
+        assert_eq!(fortune.id(), "startrek/1");
+        assert_eq!(
+            corpus.random_fortune(&options).await.unwrap().id(),
+            fortune.id()
+        );
+    }
+    #[tokio::test]
+    async fn fortune_of_the_day_is_pinned() {
+        let corpus = memory_corpus(FILES).await;
+        let options = FortuneOptions::default();
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18);
+        let today = |user| corpus.fortune_of_the_day(&options, date, user);
+        assert_eq!(today(None).await.unwrap().id(), "startrek/0");
+        assert_eq!(today(Some("paul")).await.unwrap().id(), "humor/puns/0");
+        assert_eq!(
+            today(None).await.unwrap().id(),
+            today(None).await.unwrap().id()
+        );
+    }
+    #[tokio::test]
+    async fn fortune_stats_show_the_chance_of_each_file() {
+        let percents = |corpus: &Corpus, options: &FortuneOptions| {
+            corpus
+                .fortune_stats(options)
+                .unwrap()
+                .iter()
+                .map(|fortune_stats| (fortune_stats.file(), fortune_stats.percent().round()))
+                .collect::<Vec<_>>()
+        };
+        let corpus = memory_corpus(FILES).await;
+        let mut options = FortuneOptions::default();
+        assert_eq!(
+            percents(&corpus, &options),
+            [
+                ("puns".to_owned(), 33.0),
+                ("startrek".to_owned(), 50.0),
+                ("zippy".to_owned(), 17.0)
+            ]
+        );
+        options.set_sources(Source::parse(&["30%", "zippy", "startrek"]).unwrap());
+        assert_eq!(
+            percents(&corpus, &options),
+            [("startrek".to_owned(), 70.0), ("zippy".to_owned(), 30.0)]
+        );
+        options.set_sources(vec![]).set_selection(Selection::Equal);
+        assert_eq!(
+            percents(&corpus, &options),
+            [
+                ("puns".to_owned(), 33.0),
+                ("startrek".to_owned(), 33.0),
+                ("zippy".to_owned(), 33.0)
+            ]
+        );
+        let mut files = FILES.to_vec();
+        files.push(("zippy.toml", "weight = 0.0"));
+        let corpus = memory_corpus(&files).await;
+        assert_eq!(
+            percents(&corpus, &FortuneOptions::default()),
+            [
+                ("puns".to_owned(), 40.0),
+                ("startrek".to_owned(), 60.0),
+                ("zippy".to_owned(), 0.0)
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn memory_and_directory_sources_list_the_same_files() {
+        let data_file = |text: &str| {
+            let bytes: Vec<u8> = DataFile::parse(text.as_bytes(), None, None)
+                .to_bytes()
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        };
+        let mut files = FILES.to_vec();
+        // A .dat file's bytes are only valid UTF-8 here because the offsets are small
+        let limerick = data_file("There once was a man\n%\n");
+        files.extend([
+            ("off/limerick", "There once was a man\n%\n"),
+            ("off/limerick.dat", limerick.as_str()),
+            ("off/humor/pun", "An offensive pun\n%\n"),
+            (".hidden", "Hidden\n%\n"),
+            (".hidden.dat", limerick.as_str()),
+            (".git/config", "[core]\n"),
+            ("startrek.encoding", "utf-8\n"),
+            ("zippy.toml", "title = \"Zippy\""),
+            ("zippy.idx", "not an index"),
+            ("README", "Fortunes, one per % line\n"),
+        ]);
+        let listings = corpora("listing", &files).await.map(|corpus| {
+            corpus
+                .fortune_files(Collection::All)
+                .iter()
+                .map(|fortune_file_info| {
+                    (
+                        fortune_file_info.collection_id(),
+                        fortune_file_info.category(),
+                        fortune_file_info.is_indexed(),
+                        fortune_file_info.count(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(listings[0], listings[1]);
+        assert_eq!(
+            listings[0]
+                .iter()
+                .map(|(collection_id, ..)| collection_id.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "humor/puns",
+                "startrek",
+                "zippy",
+                "off/humor/pun",
+                "off/limerick"
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn collections_filter_offensive_files() {
+        let mut files = FILES.to_vec();
+        files.push(("off/limerick", "There once was a man\n%\n"));
+        let corpus = memory_corpus(&files).await;
+        let names = |collection| {
+            corpus
+                .fortune_files(collection)
+                .iter()
+                .map(|fortune_file_info| fortune_file_info.fortune_file())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Collection::Normal), ["puns", "startrek", "zippy"]);
+        assert_eq!(names(Collection::Offensive), ["limerick"]);
+        assert_eq!(
+            names(Collection::All),
+            ["puns", "startrek", "zippy", "limerick"]
+        );
+
+        // By default, no fortune is chosen from off/
+        let mut options = FortuneOptions::default();
+        assert!(choices(&corpus, &options, 3)
+            .iter()
+            .all(|id| !id.starts_with("off/")));
+        options.set_collection(Collection::Offensive);
+        assert!(choices(&corpus, &options, 3)
+            .iter()
+            .all(|id| id == "off/limerick/0"));
+        options.set_collection(Collection::All);
+        assert!(choices(&corpus, &options, 3)
+            .iter()
+            .any(|id| id == "off/limerick/0"));
+        let stats = corpus.fortune_stats(&options).unwrap();
+        assert!(stats
+            .iter()
+            .any(|fortune_stats| fortune_stats.is_offensive()));
+    }
+    #[test]
+    fn fnv1a_is_pinned() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+    #[tokio::test]
+    async fn fortune_by_id_finds_nested_categories() {
+        let mut files = FILES.to_vec();
+        files.push(("humor/puns/bad", "Groan\n%\nDouble groan\n%\n"));
+        let corpus = memory_corpus(&files).await;
+        let fortune = corpus.fortune_by_id("humor/puns/bad/1").await.unwrap();
+        assert_eq!(fortune.fortune(), "Double groan\n");
+        assert_eq!(fortune.id(), "humor/puns/bad/1");
+        // Leading and trailing slashes are ignored
+        let fortune = corpus.fortune_by_id("/humor/puns/0/").await.unwrap();
+        assert_eq!(fortune.fortune(), "A short pun\n");
+    }
+    #[tokio::test]
+    async fn fortune_by_id_rejects_bad_ids() {
+        let corpus = memory_corpus(FILES).await;
+        for id in ["startrek", "startrek/x", "startrek/-1", ""] {
+            assert!(
+                matches!(
+                    corpus.fortune_by_id(id).await,
+                    Err(FortuneError::InvalidId { .. })
+                ),
+                "{:?}",
+                id
+            );
+        }
+        for id in ["startrek/3", "vulcan/0", "humor/0"] {
+            assert!(
+                matches!(
+                    corpus.fortune_by_id(id).await,
+                    Err(FortuneError::NoSuchFortune { .. })
+                ),
+                "{:?}",
+                id
+            );
+        }
+    }
+    #[tokio::test]
+    async fn adjacent_ids_stop_at_the_ends_of_the_file() {
+        let corpus = memory_corpus(FILES).await;
+        assert_eq!(
+            corpus.adjacent_ids("startrek/0").unwrap(),
+            (None, Some("startrek/1".to_owned()))
+        );
+        assert_eq!(
+            corpus.adjacent_ids("startrek/2").unwrap(),
+            (Some("startrek/1".to_owned()), None)
+        );
+        assert_eq!(corpus.adjacent_ids("zippy/0").unwrap(), (None, None));
+        assert!(corpus.adjacent_ids("zippy/1").is_err());
+    }
+    #[tokio::test]
+    async fn next_fortune_wraps_after_the_last_one() {
+        let text = b"Kirk\n%\nSpock\n%\nMcCoy\n%\n";
+        let data_file = DataFile::parse(text, None, Some(Flags::Ordered));
+        let mut source = MemorySource::new();
+        source.insert(Path::new("startrek"), text.to_vec()).insert(
+            Path::new("startrek.dat"),
+            data_file.to_bytes().collect::<Vec<_>>(),
+        );
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("fortune-corpus-{}", std::process::id()))
+            .join("next.tsv");
+        let _ = std::fs::remove_file(&path);
+        let cursors = Cursors::new(path);
+        let mut ids = vec![];
+        for _ in 0..4 {
+            let fortune = corpus.next_fortune("startrek", &cursors, "").await.unwrap();
+            ids.push(fortune.id());
+        }
+        assert_eq!(
+            ids,
+            ["startrek/0", "startrek/1", "startrek/2", "startrek/0"]
+        );
+        std::fs::remove_file(cursors.path()).unwrap();
+    }
+}
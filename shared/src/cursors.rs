@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use super::{FortuneError, Result};
+
+/// Where sequential readers are in the fortune files they read, kept in a state file so
+/// that each call returns the next fortune. The file has a line for each reader and fortune
+/// file, with the reader, the file's identifier and the index of the next fortune, separated
+/// by tabs. It is locked while it is read and written, so more than one process can use it
+/// at once. The lines are kept in the order they were last used, and only the most recently
+/// used `max_cursors` of them are kept, so the file can't grow without limit.
+#[derive(Clone, Debug)]
+pub struct Cursors {
+    path: PathBuf,
+    max_cursors: usize,
+}
+impl Cursors {
+    pub const DEFAULT_MAX_CURSORS: usize = 10_000;
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_cursors: Self::DEFAULT_MAX_CURSORS,
+        }
+    }
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    /// The most lines the state file keeps; the least recently used are dropped first
+    pub fn max_cursors(&self) -> usize {
+        self.max_cursors
+    }
+    pub fn set_max_cursors(&mut self, max_cursors: usize) -> &mut Self {
+        self.max_cursors = max_cursors.max(1);
+        self
+    }
+    /// Returns the index of the next fortune the reader should get from the fortune file with
+    /// the given identifier and count, and moves the reader's cursor past it. After the last
+    /// fortune, the cursor goes back to the first.
+    pub(crate) async fn advance(&self, reader: &str, id: &str, count: usize) -> Result<usize> {
+        let cursors = self.clone();
+        // Tabs and line breaks would change the state file's layout
+        let reader = reader.replace(['\t', '\r', '\n'], " ");
+        let id = id.to_owned();
+        tokio::task::spawn_blocking(move || cursors.advance_blocking(&reader, &id, count))
+            .await
+            .map_err(|error| FortuneError::io(&self.path, error.into()))?
+    }
+    fn advance_blocking(&self, reader: &str, id: &str, count: usize) -> Result<usize> {
+        let io_error = |error| FortuneError::io(&self.path, error);
+        if let Some(folder) = self
+            .path
+            .parent()
+            .filter(|folder| !folder.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(folder).map_err(io_error)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .map_err(io_error)?;
+        // The lock is released when the file is closed
+        file.lock().map_err(io_error)?;
+        let mut state = String::new();
+        file.read_to_string(&mut state).map_err(io_error)?;
+
+        let mut lines: Vec<String> = vec![];
+        let mut index = 0;
+        for line in state.lines() {
+            let mut fields = line.split('\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(line_reader), Some(line_id), Some(next))
+                    if line_reader == reader && line_id == id =>
+                {
+                    index = next.parse::<usize>().unwrap_or_default();
+                }
+                _ => lines.push(line.to_owned()),
+            }
+        }
+        // The file may have fewer fortunes than it did
+        if index >= count {
+            index = 0;
+        }
+        lines.push(format!(
+            "{}\t{}\t{}",
+            reader,
+            id,
+            (index + 1) % count.max(1)
+        ));
+        let excess = lines.len().saturating_sub(self.max_cursors);
+        lines.drain(..excess);
+        rewrite(&mut file, &lines).map_err(io_error)?;
+        Ok(index)
+    }
+}
+fn rewrite(file: &mut File, lines: &[String]) -> std::io::Result<()> {
+    let mut state = lines.join("\n");
+    state.push('\n');
+    file.seek(SeekFrom::Start(0))?;
+    file.set_len(0)?;
+    file.write_all(state.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cursors(name: &str) -> Cursors {
+        let path = std::env::temp_dir()
+            .join(format!("fortune-cursors-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_file(&path);
+        Cursors::new(path)
+    }
+
+    #[tokio::test]
+    async fn readers_have_their_own_places() {
+        let cursors = temp_cursors("places.tsv");
+        assert_eq!(cursors.advance("a", "startrek", 3).await.unwrap(), 0);
+        assert_eq!(cursors.advance("a", "startrek", 3).await.unwrap(), 1);
+        assert_eq!(cursors.advance("b", "startrek", 3).await.unwrap(), 0);
+        assert_eq!(cursors.advance("a", "startrek", 3).await.unwrap(), 2);
+        assert_eq!(cursors.advance("a", "startrek", 3).await.unwrap(), 0);
+        std::fs::remove_file(cursors.path()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_cursors_are_dropped() {
+        let mut cursors = temp_cursors("capped.tsv");
+        cursors.set_max_cursors(2);
+        cursors.advance("a", "startrek", 3).await.unwrap();
+        cursors.advance("b", "startrek", 3).await.unwrap();
+        cursors.advance("a", "startrek", 3).await.unwrap();
+        cursors.advance("c", "startrek", 3).await.unwrap();
+        let state = std::fs::read_to_string(cursors.path()).unwrap();
+        assert_eq!(state, "a\tstartrek\t2\nc\tstartrek\t1\n");
+        // b was dropped, so it starts again at the beginning, and that drops a
+        assert_eq!(cursors.advance("b", "startrek", 3).await.unwrap(), 0);
+        assert_eq!(cursors.advance("a", "startrek", 3).await.unwrap(), 0);
+        std::fs::remove_file(cursors.path()).unwrap();
+    }
+}
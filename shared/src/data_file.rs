@@ -3,20 +3,41 @@
  * Created 2024-04-30
  */
 
+use std::ops::Range;
+use std::path::Path;
 use std::vec::IntoIter;
 
+use tokio::io;
+
 pub use flags::Flags;
 pub use header::Header;
+pub use separator::Separator;
+
+use super::compression;
 
 pub mod flags;
 pub mod header;
+pub mod separator;
 
 #[derive(Debug, Default)]
 pub struct DataFile {
     pub header: Header,
     pub offsets: Vec<u32>,
+    /// What separates the fortunes. If the header's character isn't the whole separator, the
+    /// Separator flag is set and it is stored after the offsets.
+    pub separator: Separator,
+    /// The length of each fortune's separator line, if the separator is a pattern, whose
+    /// lines needn't all be the same length
+    pub separator_lengths: Vec<u32>,
 }
 impl DataFile {
+    const LINE_SEPARATOR: u32 = 1;
+    const PATTERN_SEPARATOR: u32 = 2;
+
+    /// Returns the bytes of the `.dat` file. If the Separator flag is set, the offsets are
+    /// followed by the separator: its kind, 1 for a line or 2 for a pattern, the length of its
+    /// text, the UTF-8 bytes of its text, padded to a multiple of four, and, for a pattern,
+    /// the length of each fortune's separator line.
     pub fn to_bytes(&self) -> IntoIter<u8> {
         let mut bucket = vec![];
         for byte in self.header.to_bytes() {
@@ -27,6 +48,204 @@ impl DataFile {
                 bucket.push(byte);
             }
         }
+        if self.header.flags().contains(Flags::Separator) {
+            let kind = match self.separator {
+                Separator::Line(_) => Self::LINE_SEPARATOR,
+                Separator::Pattern(_) => Self::PATTERN_SEPARATOR,
+            };
+            let mut text = self.separator.text().into_bytes();
+            bucket.extend(kind.to_be_bytes());
+            bucket.extend((text.len() as u32).to_be_bytes());
+            text.resize(text.len().next_multiple_of(4), 0);
+            bucket.extend(text);
+            for length in &self.separator_lengths {
+                bucket.extend(length.to_be_bytes());
+            }
+        }
         bucket.into_iter()
     }
+    /// Reads the separator stored after the offsets of a `.dat` file with the given number of
+    /// fortunes, given the numbers that follow the offsets. Returns the separator and the
+    /// lengths of its lines, or None if the numbers aren't a valid separator.
+    pub fn read_separator(numbers: &[u32], count: u32) -> Option<(Separator, Vec<u32>)> {
+        let [kind, length, rest @ ..] = numbers else {
+            return None;
+        };
+        let length = *length as usize;
+        let words = length.div_ceil(4);
+        let mut text: Vec<u8> = rest
+            .get(..words)?
+            .iter()
+            .flat_map(|number| number.to_be_bytes())
+            .collect();
+        text.truncate(length);
+        let text = String::from_utf8(text).ok()?;
+        let lengths = &rest[words..];
+        match *kind {
+            Self::LINE_SEPARATOR if lengths.is_empty() => Some((Separator::Line(text), vec![])),
+            Self::PATTERN_SEPARATOR if lengths.len() == count as usize => {
+                Some((Separator::pattern(&text).ok()?, lengths.to_vec()))
+            }
+            _ => None,
+        }
+    }
+    /// Returns the range of the text that holds the fortune at the given index, which doesn't
+    /// include its separator line, or None if the offsets don't have room for it.
+    pub fn fortune_range(&self, index: usize) -> Option<Range<usize>> {
+        let start = *self.offsets.get(index)? as usize;
+        let end = *self.offsets.get(index + 1)? as usize;
+        let separator_length = match self.separator.line_length() {
+            Some(length) => length,
+            None => *self.separator_lengths.get(index)? as usize,
+        };
+        let end = end.checked_sub(separator_length)?;
+        (start <= end).then_some(start..end)
+    }
+
+    /// Reads a fortune file and builds the index for it. The fortunes in the file are
+    /// separated by lines that match the separator, `%` by default. A compressed file,
+    /// e.g. `startrek.gz`, is indexed as it is once it is decompressed.
+    pub async fn build<P: AsRef<Path>>(
+        in_file: P,
+        separator: Option<Separator>,
+        flags: Option<Flags>,
+    ) -> io::Result<DataFile> {
+        let text = compression::read_text(in_file.as_ref()).await?;
+        Ok(Self::parse(&text, separator, flags))
+    }
+    /// Builds the index for the text of a fortune file.
+    pub fn parse(text: &[u8], separator: Option<Separator>, flags: Option<Flags>) -> DataFile {
+        let separator = separator.unwrap_or_default();
+        let mut flags = flags.unwrap_or_default();
+        flags.set(Flags::Separator, !separator.fits_header());
+
+        let mut count = u32::MIN;
+        let mut shortest = u32::MAX;
+        let mut longest = u32::MIN;
+        let mut offsets: Vec<u32> = vec![];
+        let mut separator_lengths: Vec<u32> = vec![];
+
+        // Read lines as bytes, so files in any ASCII compatible encoding can be indexed
+        let mut len = 0;
+        let mut offset = 0;
+
+        for line in text.split_inclusive(|byte| *byte == b'\n') {
+            let line_len = line.len() as u32;
+            if !separator.is_match(line) {
+                len += line_len;
+            } else {
+                // End of the entry
+                shortest = shortest.min(len);
+                longest = longest.max(len);
+                offsets.push(offset);
+                if separator.line_length().is_none() {
+                    separator_lengths.push(line_len);
+                }
+                offset += len + line_len;
+                count += 1;
+                len = 0;
+            }
+        }
+        offsets.push(offset); // End of file offset
+        let header = Header::default()
+            .set_separator(separator.header_char())
+            .set_count(count)
+            .set_longest(longest)
+            .set_shortest(if count == 0 { 0 } else { shortest })
+            .set_flags(flags)
+            .to_owned();
+        DataFile {
+            header,
+            offsets,
+            separator,
+            separator_lengths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use super::super::{parse_data_file, Corpus, FortuneOptions, MemorySource};
+    use super::*;
+
+    const TEXT: &[u8] = b"One\n---\nTwo\nlines\n--------\nThree\n-----\n";
+
+    /// Writes the `.dat` file's bytes, and reads them back.
+    fn round_trip(data_file: &DataFile) -> DataFile {
+        let bytes: Vec<u8> = data_file.to_bytes().collect();
+        parse_data_file(&PathBuf::from("test.dat"), &bytes).unwrap()
+    }
+
+    #[test]
+    fn single_character_separators_fit_the_header() {
+        let data_file = DataFile::parse(
+            b"One\n\xe2\x98\x85\nTwo\n\xe2\x98\x85\n",
+            Some('★'.into()),
+            None,
+        );
+        assert!(!data_file.header.flags().contains(Flags::Separator));
+        let read = round_trip(&data_file);
+        assert_eq!(read.separator, Separator::from('★'));
+        assert_eq!(read.offsets, [0, 8, 16]);
+        assert_eq!(read.fortune_range(1), Some(8..12));
+    }
+    #[test]
+    fn line_separators_round_trip() {
+        let data_file = DataFile::parse(
+            b"One\n%%\nTwo\n%%\n",
+            Some(Separator::Line("%%".into())),
+            None,
+        );
+        assert!(data_file.header.flags().contains(Flags::Separator));
+        let read = round_trip(&data_file);
+        assert_eq!(read.separator, data_file.separator);
+        assert!(read.separator_lengths.is_empty());
+        assert_eq!(read.fortune_range(0), Some(0..4));
+        assert_eq!(read.fortune_range(1), Some(7..11));
+    }
+    #[test]
+    fn pattern_separators_round_trip() {
+        let separator = Separator::pattern("-{3,}").unwrap();
+        let data_file = DataFile::parse(TEXT, Some(separator), None);
+        assert_eq!(data_file.header.count(), 3);
+        assert_eq!(data_file.separator_lengths, [4, 9, 6]);
+        let read = round_trip(&data_file);
+        assert_eq!(read.separator, data_file.separator);
+        assert_eq!(read.separator_lengths, data_file.separator_lengths);
+        assert_eq!(read.offsets, data_file.offsets);
+        let fortunes: Vec<&[u8]> = (0..3)
+            .map(|index| &TEXT[read.fortune_range(index).unwrap()])
+            .collect();
+        assert_eq!(fortunes, [&b"One\n"[..], b"Two\nlines\n", b"Three\n"]);
+    }
+    #[test]
+    fn read_separator_rejects_bad_numbers() {
+        let line = u32::from_be_bytes(*b"%%\0\0");
+        assert!(DataFile::read_separator(&[1, 2, line], 5).is_some());
+        // Missing text, lengths a line separator doesn't have, an unknown kind
+        assert!(DataFile::read_separator(&[1, 2], 5).is_none());
+        assert!(DataFile::read_separator(&[1, 2, line, 3], 5).is_none());
+        assert!(DataFile::read_separator(&[3, 2, line], 5).is_none());
+        // A pattern needs a length for each fortune
+        let pattern = u32::from_be_bytes(*b"-+\0\0");
+        assert!(DataFile::read_separator(&[2, 2, pattern, 4], 2).is_none());
+        assert!(DataFile::read_separator(&[2, 2, pattern, 4, 5], 2).is_some());
+    }
+    #[tokio::test]
+    async fn fortunes_are_read_with_stored_separators() {
+        let data_file = DataFile::parse(TEXT, Some(Separator::pattern("-{3,}").unwrap()), None);
+        let mut source = MemorySource::new();
+        source.insert(Path::new("dashes"), TEXT.to_vec()).insert(
+            Path::new("dashes.dat"),
+            data_file.to_bytes().collect::<Vec<_>>(),
+        );
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        let fortune = corpus.fortune_by_id("dashes/1").await.unwrap();
+        assert_eq!(fortune.fortune(), "Two\nlines\n");
+    }
 }
@@ -12,6 +12,7 @@ bitflags! {
         const Random = 0b00000001; // Are entries random?
         const Ordered = 0b00000010; // Are entries ordered?
         const Rotated = 0b00000100; // Are entries ROT13 encoded?
+        const Separator = 0b00010000; // Is the separator stored after the offsets?
     }
 }
 impl From<&Flags> for u32 {
@@ -31,6 +32,9 @@ impl Display for Flags {
         if self.contains(Flags::Rotated) {
             flags.push("Rotated")
         };
+        if self.contains(Flags::Separator) {
+            flags.push("Separator")
+        };
         write!(f, "{}", flags.join(", "))
     }
 }
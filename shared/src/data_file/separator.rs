@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::fmt::Display;
+
+use regex::bytes::Regex;
+
+use super::super::{FortuneError, Result};
+
+/// What separates the fortunes in a fortune file. Each fortune is followed by a separator
+/// line, which ends with a newline.
+#[derive(Clone, Debug)]
+pub enum Separator {
+    /// Lines that are exactly the text, e.g. `%`, `%%` or `----`
+    Line(String),
+    /// Lines that match the regular expression as a whole, e.g. `-{3,}` for lines of three or
+    /// more dashes
+    Pattern(Regex),
+}
+impl Separator {
+    /// Returns the separator for lines that match the regular expression as a whole.
+    pub fn pattern(pattern: &str) -> Result<Self> {
+        Regex::new(&format!("^(?:{})$", pattern))
+            .map(Separator::Pattern)
+            .map_err(|error| FortuneError::InvalidPattern {
+                pattern: pattern.to_owned(),
+                message: error.to_string(),
+            })
+    }
+    /// Returns true if the line, including its newline, is a separator line.
+    pub fn is_match(&self, line: &[u8]) -> bool {
+        let Some(line) = line.strip_suffix(b"\n") else {
+            return false;
+        };
+        match self {
+            Separator::Line(text) => line == text.as_bytes(),
+            Separator::Pattern(regex) => regex.is_match(line),
+        }
+    }
+    /// Returns true if the text has at least one separator line.
+    pub fn is_in(&self, text: &[u8]) -> bool {
+        text.split_inclusive(|byte| *byte == b'\n')
+            .any(|line| self.is_match(line))
+    }
+    /// Returns the length of every separator line, including its newline, if they are all
+    /// the same length.
+    pub fn line_length(&self) -> Option<usize> {
+        match self {
+            Separator::Line(text) => Some(text.len() + 1),
+            Separator::Pattern(_) => None,
+        }
+    }
+    /// Returns the character to put in a `.dat` file's header: the separator, if it is one
+    /// character, which is all that `strfile` can store, or else the first one of its text.
+    pub fn header_char(&self) -> char {
+        match self {
+            Separator::Line(text) => text.chars().next().unwrap_or_default(),
+            Separator::Pattern(_) => char::default(),
+        }
+    }
+    /// Returns true if the header's character is the whole separator, so it needn't be
+    /// stored after the offsets.
+    pub fn fits_header(&self) -> bool {
+        match self {
+            Separator::Line(text) => text.chars().count() == 1,
+            Separator::Pattern(_) => false,
+        }
+    }
+    /// Returns the separator's text, or the regular expression it was made from.
+    pub fn text(&self) -> String {
+        match self {
+            Separator::Line(text) => text.to_owned(),
+            Separator::Pattern(regex) => {
+                let pattern = regex.as_str();
+                pattern[4..pattern.len() - 2].to_owned()
+            }
+        }
+    }
+}
+impl Default for Separator {
+    fn default() -> Self {
+        Separator::Line("%".to_owned())
+    }
+}
+impl From<char> for Separator {
+    fn from(value: char) -> Self {
+        Separator::Line(value.to_string())
+    }
+}
+impl PartialEq for Separator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Separator::Line(a), Separator::Line(b)) => a == b,
+            (Separator::Pattern(a), Separator::Pattern(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+impl Display for Separator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Separator::Line(text) => write!(f, "{}", text),
+            Separator::Pattern(_) => write!(f, "/{}/", self.text()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_match_whole_separator_lines() {
+        let separator = Separator::Line("%%".to_owned());
+        assert!(separator.is_match(b"%%\n"));
+        assert!(!separator.is_match(b"%%"));
+        assert!(!separator.is_match(b"%\n"));
+        assert!(!separator.is_match(b"%%%\n"));
+        assert_eq!(separator.line_length(), Some(3));
+        assert!(!separator.fits_header());
+        assert!(Separator::from('★').fits_header());
+    }
+    #[test]
+    fn is_in_finds_a_separator_line() {
+        let separator = Separator::default();
+        assert!(separator.is_in(b"Kirk\n%\nSpock\n"));
+        assert!(!separator.is_in(b"Kirk\n%Spock\n100%\n"));
+        assert!(!separator.is_in(b"%"));
+    }
+    #[test]
+    fn patterns_match_whole_lines() {
+        let separator = Separator::pattern("-{3,}").unwrap();
+        assert!(separator.is_match(b"---\n"));
+        assert!(separator.is_match(b"--------\n"));
+        assert!(!separator.is_match(b"--\n"));
+        assert!(!separator.is_match(b"x---\n"));
+        assert_eq!(separator.line_length(), None);
+        assert_eq!(separator.text(), "-{3,}");
+        assert_eq!(separator.to_string(), "/-{3,}/");
+        assert!(matches!(
+            Separator::pattern("-{3,"),
+            Err(FortuneError::InvalidPattern { .. })
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::Fortune;
+
+/// How a fortune is laid out for display. Tabs are expanded, and the attribution is put on
+/// its own line, indented. If there is a width, paragraphs with lines wider than it are
+/// reflowed to fit, unless they look pre-formatted, e.g. poems, tables or ASCII art.
+/// Widths are in terminal columns, so wide characters such as CJK count as two.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    width: Option<usize>,
+    tab_width: usize,
+    attribution_indent: usize,
+}
+impl FormatOptions {
+    const DEFAULT_TAB_WIDTH: usize = 8;
+    const DEFAULT_ATTRIBUTION_INDENT: usize = 8;
+    /// The narrowest an attribution is wrapped to before its indent is given up
+    const MIN_ATTRIBUTION_WIDTH: usize = 20;
+
+    /// Creates options to wrap fortunes to the given width, if there is one.
+    pub fn new(width: Option<usize>) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+    pub fn set_width(&mut self, width: Option<usize>) -> &mut Self {
+        self.width = width;
+        self
+    }
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+    pub fn set_tab_width(&mut self, tab_width: usize) -> &mut Self {
+        self.tab_width = tab_width;
+        self
+    }
+    /// The number of columns before the `--` of an attribution
+    pub fn attribution_indent(&self) -> usize {
+        self.attribution_indent
+    }
+    pub fn set_attribution_indent(&mut self, attribution_indent: usize) -> &mut Self {
+        self.attribution_indent = attribution_indent;
+        self
+    }
+    /// Returns the whole text of the fortune, laid out: its body, followed by its attribution,
+    /// if it has one.
+    pub fn format(&self, fortune: &Fortune) -> String {
+        let mut text = self.format_body(&fortune.body());
+        if let Some(author) = fortune.author() {
+            text.push_str(&self.format_attribution(&author, fortune.source().as_deref()));
+        }
+        text
+    }
+    /// Returns the text of a fortune without its attribution, laid out.
+    pub fn format_body(&self, body: &str) -> String {
+        let text = expand_tabs(body, self.tab_width);
+        let Some(width) = self.width else {
+            return text;
+        };
+        let mut lines: Vec<String> = vec![];
+        for paragraph in paragraphs(&text) {
+            let too_wide = paragraph.iter().any(|line| line.width() > width);
+            if too_wide && !is_preformatted(&paragraph) {
+                lines.extend(wrap(&paragraph.join(" "), width, 0, 0));
+            } else {
+                lines.extend(paragraph.iter().map(|line| line.to_string()));
+            }
+        }
+        let mut text = lines.join("\n");
+        if body.ends_with('\n') {
+            text.push('\n');
+        }
+        text
+    }
+    /// Returns the attribution line for the author and source, e.g. `-- Mark Twain,
+    /// "Pudd'nhead Wilson's Calendar"`, indented, and wrapped so its lines line up after
+    /// the `--`.
+    pub fn format_attribution(&self, author: &str, source: Option<&str>) -> String {
+        let attribution = match source {
+            Some(source) => format!("-- {}, {}", author, source),
+            None => format!("-- {}", author),
+        };
+        let (indent, width) = match self.width {
+            Some(width) => {
+                let room = width.saturating_sub(Self::MIN_ATTRIBUTION_WIDTH);
+                (self.attribution_indent.min(room), width)
+            }
+            None => (self.attribution_indent, usize::MAX),
+        };
+        let mut text = wrap(&attribution, width, indent, indent + 3).join("\n");
+        text.push('\n');
+        text
+    }
+}
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            width: None,
+            tab_width: Self::DEFAULT_TAB_WIDTH,
+            attribution_indent: Self::DEFAULT_ATTRIBUTION_INDENT,
+        }
+    }
+}
+
+/// Replaces the tabs in the text with spaces, up to the next multiple of the tab width.
+pub fn expand_tabs(text: &str, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += c.width().unwrap_or_default();
+            }
+        }
+    }
+    expanded
+}
+
+/// Returns true if the lines look laid out by hand, so reflowing them would spoil them:
+/// some are indented, have gaps inside them to line things up, or are mostly symbols, the
+/// way ASCII art is. Their tabs should already be expanded, as [expand_tabs] does.
+pub fn is_preformatted<S: AsRef<str>>(lines: &[S]) -> bool {
+    lines.iter().any(|line| {
+        let line = line.as_ref().trim_end();
+        let text = line.trim_start();
+        let symbols = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_alphanumeric())
+            .count();
+        let visible = text.chars().filter(|c| !c.is_whitespace()).count();
+        (!text.is_empty() && text.len() != line.len())
+            || text.contains("   ")
+            || (visible >= 4 && symbols * 2 > visible)
+    })
+}
+
+/// Splits the text into paragraphs, i.e. the runs of lines between blank lines. Each blank
+/// line is a paragraph of its own, so joining all the lines gives the text back.
+fn paragraphs(text: &str) -> Vec<Vec<&str>> {
+    let mut paragraphs: Vec<Vec<&str>> = vec![];
+    let mut paragraph = vec![];
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(std::mem::take(&mut paragraph));
+            }
+            paragraphs.push(vec![line]);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    paragraphs
+}
+
+/// Fills lines no wider than the width with the words of the text, the first indented by
+/// `first_indent` columns and the rest by `indent`. A word too wide for a line of its own is
+/// broken between characters.
+fn wrap(text: &str, width: usize, first_indent: usize, indent: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = " ".repeat(first_indent);
+    let mut line_width = first_indent;
+    let mut empty = true;
+    for word in text.split_whitespace() {
+        let word_width = word.width();
+        let space = usize::from(!empty);
+        if !empty && line_width + space + word_width > width {
+            lines.push(std::mem::replace(&mut line, " ".repeat(indent)));
+            line_width = indent;
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+            line_width += 1;
+        }
+        for c in word.chars() {
+            let char_width = c.width().unwrap_or_default();
+            if !empty && line_width + char_width > width {
+                lines.push(std::mem::replace(&mut line, " ".repeat(indent)));
+                line_width = indent;
+            }
+            line.push(c);
+            line_width += char_width;
+            empty = false;
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_tabs_to_the_next_tab_stop() {
+        assert_eq!(expand_tabs("ab\tcd\te\n\tf", 4), "ab  cd  e\n    f");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        assert_eq!(expand_tabs("a\tb", 8), "a       b");
+        // Wide characters take two columns
+        assert_eq!(expand_tabs("日\tx", 4), "日  x");
+        // A tab width of 0 is taken to be 1
+        assert_eq!(expand_tabs("a\tb", 0), "a b");
+    }
+    #[test]
+    fn is_preformatted_finds_indents_gaps_and_art() {
+        assert!(!is_preformatted(&[
+            "Plain text, with punctuation.",
+            "More text"
+        ]));
+        assert!(!is_preformatted(&["", "Trailing spaces   "]));
+        assert!(is_preformatted(&["Roses are red,", "  Violets are blue"]));
+        assert!(is_preformatted(&["Name    Rank", "Kirk    Captain"]));
+        assert!(is_preformatted(&[r" /\_/\", r"( o.o )", r" > ^ <"]));
+        assert!(is_preformatted(&[expand_tabs("\tIndented", 8)]));
+    }
+    #[test]
+    fn paragraphs_split_at_blank_lines() {
+        assert_eq!(
+            paragraphs("One\ntwo\n\n  \nThree\n"),
+            [vec!["One", "two"], vec![""], vec!["  "], vec!["Three"]]
+        );
+        assert!(paragraphs("").is_empty());
+    }
+    #[test]
+    fn wrap_fills_lines_to_the_width() {
+        assert_eq!(
+            wrap("The quick brown fox jumps", 10, 0, 0),
+            ["The quick", "brown fox", "jumps"]
+        );
+        assert_eq!(
+            wrap("-- Mark Twain, Following the Equator", 20, 2, 5),
+            ["  -- Mark Twain,", "     Following the", "     Equator"]
+        );
+        assert_eq!(wrap("", 10, 0, 0), [""]);
+    }
+    #[test]
+    fn wrap_breaks_words_wider_than_the_width() {
+        assert_eq!(
+            wrap("a supercalifragilistic word", 10, 0, 0),
+            ["a", "supercalif", "ragilistic", "word"]
+        );
+        // Wide characters aren't split across lines
+        assert_eq!(wrap("日本語です", 5, 0, 0), ["日本", "語で", "す"]);
+    }
+    #[test]
+    fn wrap_to_width_0_puts_a_character_on_each_line() {
+        assert_eq!(wrap("ab cd", 0, 0, 0), ["a", "b", "c", "d"]);
+    }
+    #[test]
+    fn format_body_reflows_only_plain_paragraphs() {
+        let format_options = FormatOptions::new(Some(12));
+        let body = "The quick brown fox\n\n  jumps over the lazy dog\n";
+        assert_eq!(
+            format_options.format_body(body),
+            "The quick\nbrown fox\n\n  jumps over the lazy dog\n"
+        );
+        assert_eq!(FormatOptions::new(None).format_body(body), body);
+    }
+}
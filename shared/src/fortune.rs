@@ -4,19 +4,162 @@
  */
 use serde::Serialize;
 
+use super::FormatOptions;
+
 #[derive(Serialize)]
 pub struct Fortune {
+    id: String,
     file: String,
+    index: usize,
     fortune: String,
+    body: String,
+    author: Option<String>,
+    source: Option<String>,
 }
 impl Fortune {
-    pub fn new(file: String, fortune: String) -> Self {
-        Self { file, fortune }
+    pub fn new(id: String, file: String, index: usize, fortune: String) -> Self {
+        let (body, author, source) = match parse_attribution(&fortune) {
+            Some((body, author, source)) => (body, Some(author), source),
+            None => (fortune.to_owned(), None, None),
+        };
+        Self {
+            id,
+            file,
+            index,
+            fortune,
+            body,
+            author,
+            source,
+        }
+    }
+    /// The fortune's stable identifier, which can be used to get it again
+    pub fn id(&self) -> String {
+        self.id.to_owned()
     }
     pub fn file(&self) -> String {
         self.file.to_owned()
     }
+    /// The index of the fortune in its file
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// The whole text of the fortune, including its attribution
     pub fn fortune(&self) -> String {
         self.fortune.to_owned()
     }
+    /// The text of the fortune without its attribution
+    pub fn body(&self) -> String {
+        self.body.to_owned()
+    }
+    /// Who said or wrote the fortune, from an attribution line like `-- Mark Twain`
+    pub fn author(&self) -> Option<String> {
+        self.author.to_owned()
+    }
+    /// Where the fortune comes from, from the part of the attribution after the author's
+    /// name and a comma, e.g. `"Pudd'nhead Wilson's Calendar"`
+    pub fn source(&self) -> Option<String> {
+        self.source.to_owned()
+    }
+    /// Lays out the fortune's text and body for display, leaving its author and source as
+    /// they are.
+    pub fn format(&mut self, options: &FormatOptions) -> &mut Self {
+        self.fortune = options.format(self);
+        self.body = options.format_body(&self.body);
+        self
+    }
+    /// Returns true if the fortune's author contains the given name, ignoring case.
+    pub fn is_by(&self, name: &str) -> bool {
+        self.author
+            .as_ref()
+            .is_some_and(|author| author.to_lowercase().contains(&name.to_lowercase()))
+    }
+}
+
+/// Splits a fortune that ends with an attribution into its body, author and source. The
+/// attribution is the last line that starts with `--` or `—`, along with any indented lines
+/// after it, and the source is whatever follows the first comma. Returns `None` if the
+/// fortune has no attribution, or nothing else.
+fn parse_attribution(fortune: &str) -> Option<(String, String, Option<String>)> {
+    let lines: Vec<&str> = fortune.trim_end().lines().collect();
+    let start = lines.iter().rposition(|line| {
+        let line = line.trim_start();
+        line.starts_with("--") || line.starts_with('—')
+    })?;
+    if lines[start + 1..]
+        .iter()
+        .any(|line| !line.starts_with(char::is_whitespace))
+    {
+        return None;
+    }
+    let body = lines[..start].join("\n");
+    let body = body.trim_end();
+    if body.trim().is_empty() {
+        return None;
+    }
+    let attribution = lines[start..]
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let attribution = attribution.trim_start_matches(['-', '—']).trim();
+    let (author, source) = match attribution.split_once(',') {
+        Some((author, source)) => (author.trim(), Some(source.trim())),
+        None => (attribution, None),
+    };
+    if author.is_empty() {
+        return None;
+    }
+    Some((
+        format!("{}\n", body),
+        author.to_owned(),
+        source
+            .filter(|source| !source.is_empty())
+            .map(str::to_owned),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_attribution_with_author_and_source() {
+        let fortune = "Always do right.\n\n\t\t-- Mark Twain, \"Letter\",\n\t\t   1901\n";
+        let (body, author, source) = parse_attribution(fortune).unwrap();
+        assert_eq!(body, "Always do right.\n");
+        assert_eq!(author, "Mark Twain");
+        assert_eq!(source.as_deref(), Some("\"Letter\", 1901"));
+    }
+    #[test]
+    fn parse_attribution_with_dash_and_no_source() {
+        let (body, author, source) = parse_attribution("Yow!\n— Zippy\n").unwrap();
+        assert_eq!(
+            (body.as_str(), author.as_str(), source),
+            ("Yow!\n", "Zippy", None)
+        );
+    }
+    #[test]
+    fn parse_attribution_needs_a_body_an_author_and_the_last_line() {
+        for fortune in [
+            "No attribution here.\n",
+            "-- Mark Twain\n",
+            "Body\n-- \n",
+            "Body\n-- Mark Twain\nMore body\n",
+        ] {
+            assert_eq!(parse_attribution(fortune), None, "{:?}", fortune);
+        }
+    }
+    #[test]
+    fn new_splits_the_attribution() {
+        let fortune = Fortune::new(
+            "zippy/0".to_owned(),
+            "zippy".to_owned(),
+            0,
+            "Yow!\n-- Zippy\n".to_owned(),
+        );
+        assert_eq!(fortune.body(), "Yow!\n");
+        assert_eq!(fortune.author().as_deref(), Some("Zippy"));
+        assert!(fortune.is_by("zip"));
+        assert_eq!(fortune.fortune(), "Yow!\n-- Zippy\n");
+    }
 }
@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// The ways that reading fortunes from the data files can fail.
+/// Every variant except [FortuneError::InvalidSource], [FortuneError::InvalidPattern],
+/// [FortuneError::InvalidQuery] and [FortuneError::InvalidId] carries the path of the
+/// offending file or folder.
+#[derive(Debug)]
+pub enum FortuneError {
+    /// The data folder does not exist or cannot be read
+    DataFolder { path: PathBuf, source: io::Error },
+    /// The `.dat` file has a version this library doesn't understand
+    BadVersion { path: PathBuf, version: u32 },
+    /// The `.dat` file ends before its header and offsets have been read, or the separator
+    /// stored after them can't be read
+    TruncatedHeader { path: PathBuf },
+    /// A fortune's offsets are missing, out of order, or outside the text file
+    OffsetOutOfRange { path: PathBuf, index: usize },
+    /// The text file that goes with a `.dat` file does not exist
+    MissingTextFile { path: PathBuf },
+    /// A fortune's text is not valid for its encoding
+    InvalidEncoding { path: PathBuf, index: usize },
+    /// A fortune file's declared encoding is unknown, or isn't supported
+    UnknownEncoding { path: PathBuf, label: String },
+    /// A fortune file's manifest isn't valid TOML, or has a field of the wrong type
+    InvalidManifest { path: PathBuf, message: String },
+    /// There are no fortunes to choose from
+    EmptyCorpus { path: PathBuf },
+    /// A source specification can't be parsed
+    InvalidSource { spec: String },
+    /// A source names a fortune file or category that isn't in the data folder
+    UnknownSource { path: PathBuf, name: String },
+    /// The sources' percentages add up to more than 100
+    PercentOver { path: PathBuf, total: f64 },
+    /// A search or separator pattern isn't a valid regular expression
+    InvalidPattern { pattern: String, message: String },
+    /// A word query can't be parsed
+    InvalidQuery { query: String, message: String },
+    /// A fortune identifier isn't of the form `<file>/<index>`
+    InvalidId { id: String },
+    /// There is no fortune with the identifier in the data folder
+    NoSuchFortune { path: PathBuf, id: String },
+    /// A fortune file is read in order, but its `.dat` file doesn't have the Ordered flag
+    NotOrdered { path: PathBuf, name: String },
+    /// Any other I/O error
+    Io { path: PathBuf, source: io::Error },
+}
+impl FortuneError {
+    /// Returns the path of the file or folder that caused the error, if there is one.
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            FortuneError::DataFolder { path, .. }
+            | FortuneError::BadVersion { path, .. }
+            | FortuneError::TruncatedHeader { path }
+            | FortuneError::OffsetOutOfRange { path, .. }
+            | FortuneError::MissingTextFile { path }
+            | FortuneError::InvalidEncoding { path, .. }
+            | FortuneError::UnknownEncoding { path, .. }
+            | FortuneError::InvalidManifest { path, .. }
+            | FortuneError::EmptyCorpus { path }
+            | FortuneError::UnknownSource { path, .. }
+            | FortuneError::PercentOver { path, .. }
+            | FortuneError::NoSuchFortune { path, .. }
+            | FortuneError::NotOrdered { path, .. }
+            | FortuneError::Io { path, .. } => Some(path),
+            FortuneError::InvalidSource { .. }
+            | FortuneError::InvalidPattern { .. }
+            | FortuneError::InvalidQuery { .. }
+            | FortuneError::InvalidId { .. } => None,
+        }
+    }
+    pub(crate) fn io(path: &PathBuf, source: io::Error) -> Self {
+        FortuneError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+impl Display for FortuneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FortuneError::DataFolder { path, source } => {
+                write!(
+                    f,
+                    "Cannot read data folder '{}': {}",
+                    path.display(),
+                    source
+                )
+            }
+            FortuneError::BadVersion { path, version } => write!(
+                f,
+                "Unsupported data file version {} in '{}'",
+                version,
+                path.display()
+            ),
+            FortuneError::TruncatedHeader { path } => {
+                write!(f, "Data file '{}' is truncated", path.display())
+            }
+            FortuneError::OffsetOutOfRange { path, index } => write!(
+                f,
+                "Offset of fortune {} is out of range in '{}'",
+                index,
+                path.display()
+            ),
+            FortuneError::MissingTextFile { path } => {
+                write!(f, "Fortune file '{}' is missing", path.display())
+            }
+            FortuneError::InvalidEncoding { path, index } => write!(
+                f,
+                "Fortune {} in '{}' has an invalid encoding",
+                index,
+                path.display()
+            ),
+            FortuneError::UnknownEncoding { path, label } => write!(
+                f,
+                "Unknown or unsupported encoding '{}' in '{}'",
+                label,
+                path.display()
+            ),
+            FortuneError::InvalidManifest { path, message } => write!(
+                f,
+                "Invalid manifest '{}': {}",
+                path.display(),
+                message.trim()
+            ),
+            FortuneError::EmptyCorpus { path } => {
+                write!(
+                    f,
+                    "There are no fortunes to choose from in '{}'",
+                    path.display()
+                )
+            }
+            FortuneError::InvalidSource { spec } => {
+                write!(f, "Invalid source specification '{}'", spec)
+            }
+            FortuneError::UnknownSource { path, name } => write!(
+                f,
+                "There is no fortune file or category named '{}' in '{}'",
+                name,
+                path.display()
+            ),
+            FortuneError::PercentOver { path, total } => write!(
+                f,
+                "The percentages for '{}' add up to {}%, which is more than 100%",
+                path.display(),
+                total
+            ),
+            FortuneError::InvalidPattern { pattern, message } => {
+                write!(f, "Invalid pattern '{}': {}", pattern, message)
+            }
+            FortuneError::InvalidQuery { query, message } => {
+                write!(f, "Invalid query '{}': {}", query, message)
+            }
+            FortuneError::InvalidId { id } => write!(f, "Invalid fortune identifier '{}'", id),
+            FortuneError::NoSuchFortune { path, id } => {
+                write!(f, "There is no fortune '{}' in '{}'", id, path.display())
+            }
+            FortuneError::NotOrdered { path, name } => write!(
+                f,
+                "Fortune file '{}' can't be read in order, because '{}' isn't marked as ordered",
+                name,
+                path.display()
+            ),
+            FortuneError::Io { path, source } => write!(f, "'{}': {}", path.display(), source),
+        }
+    }
+}
+impl std::error::Error for FortuneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FortuneError::DataFolder { source, .. } | FortuneError::Io { source, .. } => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, FortuneError>;
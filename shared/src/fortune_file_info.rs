@@ -2,26 +2,198 @@
  * Copyright (c) 2024 Paul Sobolik
  * Created 2024-05-02
  */
+use std::future::Future;
+use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use super::DataFile;
+use tokio::sync::OnceCell;
+
+use super::compression::compressed_path;
+use super::{
+    Compression, DataFile, Encoding, FortuneSource, Manifest, Result, WordIndex, OFFENSIVE_FOLDER,
+};
+
+/// A fortune file's text, when it is kept in memory.
+pub(crate) enum LoadedText {
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    Shared(Arc<[u8]>),
+}
+impl Deref for LoadedText {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "mmap")]
+            LoadedText::Mapped(mmap) => mmap,
+            LoadedText::Shared(bytes) => bytes,
+        }
+    }
+}
 
 pub struct FortuneFileInfo {
     pub path: PathBuf,
     pub data_file: DataFile,
+    pub offensive: bool,
+    pub indexed: bool,
+    pub category: Option<String>,
+    pub encoding: Option<&'static Encoding>,
+    pub manifest: Option<Manifest>,
+    pub compression: Option<Compression>,
+    word_index: OnceCell<Option<WordIndex>>,
+    loaded_text: OnceCell<LoadedText>,
 }
 impl FortuneFileInfo {
-    pub fn new(path: PathBuf, data_file: DataFile) -> Self {
-        Self { path, data_file }
+    pub fn new(path: PathBuf, data_file: DataFile, offensive: bool) -> Self {
+        Self {
+            path,
+            data_file,
+            offensive,
+            indexed: true,
+            category: None,
+            encoding: None,
+            manifest: None,
+            compression: None,
+            word_index: OnceCell::new(),
+            loaded_text: OnceCell::new(),
+        }
     }
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+    /// Returns true if the file is in the offensive folder, or its manifest says it is offensive.
+    pub fn is_offensive(&self) -> bool {
+        self.offensive || self.manifest.as_ref().is_some_and(Manifest::is_offensive)
+    }
+    /// Returns false if the file has no `.dat` file, and was indexed in memory.
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+    pub fn set_indexed(&mut self, indexed: bool) -> &mut Self {
+        self.indexed = indexed;
+        self
+    }
+    /// Returns the path of the subfolder that the file is in, relative to the data folder
+    /// (or its offensive subfolder), e.g. `humor/puns`. Files that aren't in a subfolder
+    /// have no category.
+    pub fn category(&self) -> Option<String> {
+        self.category.to_owned()
+    }
+    pub fn set_category(&mut self, category: Option<String>) -> &mut Self {
+        self.category = category;
+        self
+    }
+    /// Returns the encoding declared for the text file, if there is one.
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.encoding
+    }
+    pub fn set_encoding(&mut self, encoding: Option<&'static Encoding>) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+    /// Returns the file's manifest, if it has one.
+    pub fn manifest(&self) -> Option<&Manifest> {
+        self.manifest.as_ref()
+    }
+    pub fn set_manifest(&mut self, manifest: Option<Manifest>) -> &mut Self {
+        self.manifest = manifest;
+        self
+    }
+    /// Returns the way the text file is compressed, if it is.
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+    pub fn set_compression(&mut self, compression: Option<Compression>) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+    /// Returns the weight given to the file in its manifest, or 1 if it has none.
+    pub fn weight(&self) -> f64 {
+        self.manifest
+            .as_ref()
+            .and_then(Manifest::weight)
+            .map_or(1.0, |weight| weight.max(0.0))
+    }
+    /// Returns the file's word index, if the source has one for it. It is read the first time
+    /// it is needed, and kept.
+    pub(crate) async fn word_index(&self, source: &dyn FortuneSource) -> Option<&WordIndex> {
+        self.word_index
+            .get_or_init(|| source.word_index(self))
+            .await
+            .as_ref()
+    }
+    pub(crate) fn set_word_index(&mut self, word_index: Option<WordIndex>) -> &mut Self {
+        self.word_index = OnceCell::new_with(Some(word_index));
+        self
+    }
+    /// Returns the text file's contents, if they are in memory, because the file is memory
+    /// mapped, its source keeps it in memory, or it was compressed and has been decompressed.
+    pub fn loaded_text(&self) -> Option<&[u8]> {
+        self.loaded_text.get().map(Deref::deref)
+    }
+    pub(crate) fn set_loaded_text(&mut self, loaded_text: Option<LoadedText>) -> &mut Self {
+        self.loaded_text = OnceCell::new_with(loaded_text);
+        self
+    }
+    /// Returns the text file's contents, loading them into memory with the given function
+    /// the first time, and keeping them.
+    pub(crate) async fn load_text<F, L>(&self, load: L) -> Result<&[u8]>
+    where
+        F: Future<Output = Result<LoadedText>>,
+        L: FnOnce() -> F,
+    {
+        self.loaded_text
+            .get_or_try_init(load)
+            .await
+            .map(Deref::deref)
+    }
+    /// Returns true if the given name is the file's name, or its category and name, e.g.
+    /// `startrek` or `humor/puns`, but not just its category.
+    pub fn is_named(&self, name: &str) -> bool {
+        let name = name.trim_matches('/');
+        name == self.fortune_file()
+            || self
+                .category
+                .as_ref()
+                .is_some_and(|category| name == format!("{}/{}", category, self.fortune_file()))
+    }
+    /// Returns true if the given name is the file's name, its category or a category that
+    /// contains it, or its category and name, e.g. `startrek`, `humor`, or `humor/puns`.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim_matches('/');
+        if name == self.fortune_file() {
+            return true;
+        }
+        let Some(category) = &self.category else {
+            return false;
+        };
+        name == category
+            || category
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('/'))
+            || name == format!("{}/{}", category, self.fortune_file())
+    }
     pub fn count(&self) -> usize {
         self.data_file.header.count() as usize
     }
-    pub fn offset_at(&self, index: usize) -> u32 {
-        self.data_file.offsets[index]
+    pub fn offset_at(&self, index: usize) -> Option<u32> {
+        self.data_file.offsets.get(index).copied()
+    }
+    /// Returns the length of the fortune at the given index, not including the separator line.
+    pub fn length_at(&self, index: usize) -> Option<usize> {
+        self.data_file.fortune_range(index).map(|range| range.len())
+    }
+    /// Returns the path of the text file that the `.dat` file indexes.
+    pub fn text_path(&self) -> PathBuf {
+        self.path.with_extension("")
+    }
+    /// Returns the path of the file that holds the text: the compressed text file, if the text
+    /// is compressed, or else the text file.
+    pub fn stored_text_path(&self) -> PathBuf {
+        match self.compression {
+            Some(compression) => compressed_path(&self.text_path(), compression),
+            None => self.text_path(),
+        }
     }
     pub fn fortune_file(&self) -> String {
         let file = self.path.with_extension("");
@@ -31,4 +203,23 @@ impl FortuneFileInfo {
             .unwrap_or_default()
             .to_owned()
     }
+    /// Returns the name that identifies the fortune file in its data folder,
+    /// e.g. `startrek`, `humor/puns`, or `off/limerick` for an offensive file.
+    pub fn collection_id(&self) -> String {
+        let mut collection_id = String::new();
+        if self.is_offensive() {
+            collection_id.push_str(OFFENSIVE_FOLDER);
+            collection_id.push('/');
+        }
+        if let Some(category) = &self.category {
+            collection_id.push_str(category);
+            collection_id.push('/');
+        }
+        collection_id.push_str(&self.fortune_file());
+        collection_id
+    }
+    /// Returns the identifier of the fortune at the given index, e.g. `startrek/12`.
+    pub fn fortune_id(&self, index: usize) -> String {
+        format!("{}/{}", self.collection_id(), index)
+    }
 }
@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use super::{Decoding, Source};
+
+/// How a fortune file is chosen before a fortune is chosen from it.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Selection {
+    /// Each fortune is equally likely, so bigger files are chosen more often
+    #[default]
+    Weighted,
+    /// Each file is equally likely, no matter how many fortunes it has (like `fortune -e`)
+    Equal,
+}
+
+/// Which fortune collections to use. Offensive fortune files are kept in the `off`
+/// subfolder of the data folder, and are only used when asked for.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Collection {
+    /// Only the fortune files that aren't offensive
+    #[default]
+    Normal,
+    /// Only the offensive fortune files (like `fortune -o`)
+    Offensive,
+    /// All the fortune files (like `fortune -a`)
+    All,
+}
+impl Collection {
+    pub fn includes_normal(&self) -> bool {
+        *self != Collection::Offensive
+    }
+    pub fn includes_offensive(&self) -> bool {
+        *self != Collection::Normal
+    }
+}
+
+/// Options that control which fortune files are used, and how a fortune is chosen from them.
+#[derive(Clone, Debug, Default)]
+pub struct FortuneOptions {
+    selection: Selection,
+    sources: Vec<Source>,
+    collection: Collection,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    seed: Option<u64>,
+    strict: bool,
+    decoding: Decoding,
+    #[cfg(feature = "mmap")]
+    mapped: bool,
+}
+impl FortuneOptions {
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+    pub fn set_selection(&mut self, selection: Selection) -> &mut Self {
+        self.selection = selection;
+        self
+    }
+    /// The fortune files to choose from. If it's empty, all the files are used.
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+    pub fn set_sources(&mut self, sources: Vec<Source>) -> &mut Self {
+        self.sources = sources;
+        self
+    }
+    pub fn collection(&self) -> Collection {
+        self.collection
+    }
+    pub fn set_collection(&mut self, collection: Collection) -> &mut Self {
+        self.collection = collection;
+        self
+    }
+    /// Only fortunes at least this long, in bytes, are chosen
+    pub fn min_length(&self) -> Option<usize> {
+        self.min_length
+    }
+    pub fn set_min_length(&mut self, min_length: Option<usize>) -> &mut Self {
+        self.min_length = min_length;
+        self
+    }
+    /// Only fortunes at most this long, in bytes, are chosen
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+    pub fn set_max_length(&mut self, max_length: Option<usize>) -> &mut Self {
+        self.max_length = max_length;
+        self
+    }
+    /// The seed for the random number generator, to make the choice reproducible
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    pub fn set_seed(&mut self, seed: Option<u64>) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+    /// If true, only fortune files that have a `.dat` file are used
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+    /// What to do with fortunes that aren't valid in their files' encodings
+    pub fn decoding(&self) -> Decoding {
+        self.decoding
+    }
+    pub fn set_decoding(&mut self, decoding: Decoding) -> &mut Self {
+        self.decoding = decoding;
+        self
+    }
+    /// If true, the fortune text files are memory mapped when they are loaded, and fortunes
+    /// are read from memory
+    #[cfg(feature = "mmap")]
+    pub fn mapped(&self) -> bool {
+        self.mapped
+    }
+    #[cfg(feature = "mmap")]
+    pub fn set_mapped(&mut self, mapped: bool) -> &mut Self {
+        self.mapped = mapped;
+        self
+    }
+}
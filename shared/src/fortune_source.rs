@@ -0,0 +1,79 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+pub use archive::ArchiveSource;
+pub use directory::DirectorySource;
+#[cfg(feature = "embedded")]
+pub use embedded::EmbeddedSource;
+pub use layered::LayeredSource;
+pub use memory::MemorySource;
+
+use super::{fortune_slice, FortuneError, FortuneFileInfo, FortuneOptions, Result, WordIndex};
+
+pub mod archive;
+pub mod directory;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+pub mod layered;
+pub mod memory;
+
+/// Where a [Corpus](crate::Corpus) gets its fortune files, and the fortunes in them. The
+/// fortune files it lists have the indexes of their fortunes, so their counts are known
+/// without reading them, and a fortune is read by its index.
+#[async_trait]
+pub trait FortuneSource: Send + Sync {
+    /// The path that errors report, e.g. the data folder.
+    fn path(&self) -> &PathBuf;
+    /// Returns the fortune files in the source. Unless the options are strict, text files
+    /// without `.dat` files are included, and indexed as they are loaded.
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>>;
+    /// Returns the bytes of the fortune at the given index in one of the source's fortune
+    /// files. They aren't decoded, so they are still ROT13 encoded if the file is rotated.
+    async fn fortune_bytes<'a>(
+        &'a self,
+        fortune_file_info: &'a FortuneFileInfo,
+        index: usize,
+    ) -> Result<Cow<'a, [u8]>> {
+        let text = self.text(fortune_file_info).await?;
+        match text {
+            Cow::Borrowed(text) => fortune_slice(fortune_file_info, index, text).map(Cow::Borrowed),
+            Cow::Owned(text) => fortune_slice(fortune_file_info, index, &text)
+                .map(|bytes| Cow::Owned(bytes.to_vec())),
+        }
+    }
+    /// Returns the whole text of one of the source's fortune files.
+    async fn text<'a>(&'a self, fortune_file_info: &'a FortuneFileInfo) -> Result<Cow<'a, [u8]>> {
+        fortune_file_info
+            .loaded_text()
+            .map(Cow::Borrowed)
+            .ok_or_else(|| FortuneError::MissingTextFile {
+                path: fortune_file_info.text_path(),
+            })
+    }
+    /// Returns the word index of one of the source's fortune files, if it has one.
+    async fn word_index(&self, _fortune_file_info: &FortuneFileInfo) -> Option<WordIndex> {
+        None
+    }
+    /// Returns a number that changes whenever the source's fortune files do, so a corpus
+    /// loaded from it can tell when it is out of date.
+    async fn fingerprint(&self) -> u64 {
+        0
+    }
+}
+
+/// Returns the source for the given paths: an [ArchiveSource] if there is only one, and it
+/// is a file, or else a [DirectorySource] for the data folders, of which there must be at
+/// least one.
+pub fn source_for_paths(data_paths: &[PathBuf]) -> Result<Arc<dyn FortuneSource>> {
+    match data_paths {
+        [path] if path.is_file() => Ok(Arc::new(ArchiveSource::new(path.to_owned()))),
+        _ => Ok(Arc::new(DirectorySource::new(data_paths.to_vec())?)),
+    }
+}
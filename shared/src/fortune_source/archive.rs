@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use super::super::{FortuneError, FortuneFileInfo, FortuneOptions, Result};
+use super::{FortuneSource, MemorySource};
+
+/// Fortune files in a tar archive of a data folder, e.g. one made with
+/// `tar -C /usr/share/games/fortunes -cf fortunes.tar .`. The archive is read into memory
+/// when the fortune files are listed, so fortunes are read without any file I/O.
+pub struct ArchiveSource {
+    path: PathBuf,
+}
+impl ArchiveSource {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+    /// Reads the archive into a [MemorySource].
+    async fn read(&self) -> Result<MemorySource> {
+        let bytes = fs::read(&self.path)
+            .await
+            .map_err(|source| FortuneError::DataFolder {
+                path: self.path.to_owned(),
+                source,
+            })?;
+        read_archive(&self.path, &bytes)
+    }
+}
+#[async_trait]
+impl FortuneSource for ArchiveSource {
+    fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>> {
+        self.read().await?.fortune_files(options).await
+    }
+    /// Hashes the archive's size and modification time.
+    async fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        if let Ok(metadata) = fs::metadata(&self.path).await {
+            (metadata.len(), metadata.modified().ok()).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Reads the regular files in a tar archive with the given path into a [MemorySource].
+pub(crate) fn read_archive(path: &PathBuf, bytes: &[u8]) -> Result<MemorySource> {
+    let io_error = |error| FortuneError::io(path, error);
+    let mut source = MemorySource::with_path(path.to_owned());
+    let mut archive = tar::Archive::new(bytes);
+    for entry in archive.entries().map_err(io_error)? {
+        let mut entry = entry.map_err(io_error)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = entry.path().map_err(io_error)?.into_owned();
+        let mut contents = vec![];
+        entry.read_to_end(&mut contents).map_err(io_error)?;
+        source.insert(&entry_path, contents);
+    }
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Corpus;
+
+    /// Returns a tar archive of the given files, which are named as `tar -C <folder> -cf .`
+    /// names them.
+    fn archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, format!("./{}", path), contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_archive_lays_out_a_data_folder() {
+        let bytes = archive(&[
+            ("startrek", "Kirk\n%\nSpock\n%\n"),
+            ("humor/puns", "A short pun\n%\n"),
+            ("off/limerick", "There once was\n%\n"),
+        ]);
+        let source = read_archive(&PathBuf::from("fortunes.tar"), &bytes).unwrap();
+        let fortune_files = source
+            .fortune_files(&FortuneOptions::default())
+            .await
+            .unwrap();
+        let ids: Vec<String> = fortune_files
+            .iter()
+            .map(FortuneFileInfo::collection_id)
+            .collect();
+        assert_eq!(ids, ["humor/puns", "startrek", "off/limerick"]);
+    }
+    #[tokio::test]
+    async fn archive_source_reads_fortunes_from_the_archive() {
+        let path = std::env::temp_dir()
+            .join(format!("fortune-archive-{}", std::process::id()))
+            .join("fortunes.tar");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, archive(&[("startrek", "Kirk\n%\nSpock\n%\n")])).unwrap();
+        let source = ArchiveSource::new(path.to_owned());
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        let fortune = corpus.fortune_by_id("startrek/1").await.unwrap();
+        assert_eq!(fortune.fortune(), "Spock\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[tokio::test]
+    async fn empty_archives_have_no_fortunes() {
+        // As the build makes when there is no data folder to embed
+        let source = read_archive(&PathBuf::from("embedded.tar"), &archive(&[])).unwrap();
+        let options = FortuneOptions::default();
+        assert!(source.fortune_files(&options).await.unwrap().is_empty());
+        let corpus = Corpus::load_source(Arc::new(source), &options)
+            .await
+            .unwrap();
+        assert!(matches!(
+            corpus.random_fortune(&options).await,
+            Err(FortuneError::EmptyCorpus { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn corrupt_archives_are_errors() {
+        let bytes = archive(&[("startrek", "Kirk\n%\n")]);
+        assert!(read_archive(&PathBuf::from("fortunes.tar"), &bytes[..600]).is_err());
+    }
+}
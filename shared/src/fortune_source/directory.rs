@@ -0,0 +1,285 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use super::super::{
+    compression, fortune_files, fortune_range, fortune_slice, get_fortune, listing, out_of_range,
+    text_error, word_index, FortuneError, FortuneFileInfo, FortuneOptions, LoadedText, Result,
+    WordIndex,
+};
+use super::FortuneSource;
+
+/// Fortune files in one or more data folders on disk, the way the `fortune` program keeps them.
+pub struct DirectorySource {
+    data_paths: Vec<PathBuf>,
+}
+impl DirectorySource {
+    /// Creates a source for the given data folders, in order. If more than one has a fortune
+    /// file with the same identifier, the one in the earliest folder is used. There must be
+    /// at least one.
+    pub fn new(data_paths: Vec<PathBuf>) -> Result<Self> {
+        if data_paths.is_empty() {
+            return Err(FortuneError::DataFolder {
+                path: PathBuf::new(),
+                source: std::io::Error::new(ErrorKind::NotFound, "No data folders were given"),
+            });
+        }
+        Ok(Self { data_paths })
+    }
+    pub fn data_paths(&self) -> &[PathBuf] {
+        &self.data_paths
+    }
+    /// Returns the fortune file's text if it is in memory. A compressed text file is
+    /// decompressed into memory the first time it is read, since it can't be read in pieces.
+    async fn loaded_text<'a>(
+        &self,
+        fortune_file_info: &'a FortuneFileInfo,
+    ) -> Result<Option<&'a [u8]>> {
+        if fortune_file_info.compression().is_none() {
+            return Ok(fortune_file_info.loaded_text());
+        }
+        let path = fortune_file_info.stored_text_path();
+        fortune_file_info
+            .load_text(|| async {
+                compression::read_text(&path)
+                    .await
+                    .map(|text| LoadedText::Shared(text.into()))
+                    .map_err(|error| text_error(&path, error))
+            })
+            .await
+            .map(Some)
+    }
+}
+#[async_trait]
+impl FortuneSource for DirectorySource {
+    /// Returns the first data folder.
+    fn path(&self) -> &PathBuf {
+        &self.data_paths[0]
+    }
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>> {
+        fortune_files(&self.data_paths, options).await
+    }
+    /// Reads just the fortune from the text file, unless its text is in memory.
+    async fn fortune_bytes<'a>(
+        &'a self,
+        fortune_file_info: &'a FortuneFileInfo,
+        index: usize,
+    ) -> Result<Cow<'a, [u8]>> {
+        if let Some(text) = self.loaded_text(fortune_file_info).await? {
+            return fortune_slice(fortune_file_info, index, text).map(Cow::Borrowed);
+        }
+        let (start, len) = fortune_range(fortune_file_info, index)?;
+        let text_path = fortune_file_info.text_path();
+        get_fortune(&text_path, start, len)
+            .await
+            .map(Cow::Owned)
+            .map_err(|error| match error.kind() {
+                ErrorKind::UnexpectedEof => out_of_range(fortune_file_info, index),
+                _ => text_error(&text_path, error),
+            })
+    }
+    async fn text<'a>(&'a self, fortune_file_info: &'a FortuneFileInfo) -> Result<Cow<'a, [u8]>> {
+        if let Some(text) = self.loaded_text(fortune_file_info).await? {
+            return Ok(Cow::Borrowed(text));
+        }
+        let text_path = fortune_file_info.text_path();
+        fs::read(&text_path)
+            .await
+            .map(Cow::Owned)
+            .map_err(|error| text_error(&text_path, error))
+    }
+    async fn word_index(&self, fortune_file_info: &FortuneFileInfo) -> Option<WordIndex> {
+        word_index::read_word_index(
+            fortune_file_info.path(),
+            &fortune_file_info.stored_text_path(),
+            fortune_file_info.count(),
+        )
+        .await
+    }
+    /// Hashes the name, size and modification time of every file in the data folders.
+    async fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        Fingerprint::take(&self.data_paths).await.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// The name, size and modification time of every file in the data folders and their
+/// subfolders, other than hidden ones, which are never loaded.
+#[derive(Debug, Default, Hash, PartialEq)]
+struct Fingerprint(Vec<(PathBuf, u64, Option<SystemTime>)>);
+impl Fingerprint {
+    async fn take(data_paths: &[PathBuf]) -> Self {
+        let mut files = vec![];
+        for data_path in data_paths {
+            // A folder that can't be read has no files, until it can be
+            for path in listing::walk(data_path).await.unwrap_or_default() {
+                let path = data_path.join(path);
+                if let Ok(metadata) = fs::metadata(&path).await {
+                    files.push((path, metadata.len(), metadata.modified().ok()));
+                }
+            }
+        }
+        Self(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::super::{Collection, Corpus, DataFile};
+    use super::*;
+
+    /// Makes an empty folder for the test with the given name.
+    fn temp_folder(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("fortune-directory-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn fingerprint_changes_with_the_files() {
+        let data_path = temp_folder("fingerprint");
+        std::fs::create_dir(data_path.join("humor")).unwrap();
+        std::fs::write(data_path.join("startrek"), "Kirk\n%\n").unwrap();
+        let source = DirectorySource::new(vec![data_path.clone()]).unwrap();
+        let fingerprint = source.fingerprint().await;
+        assert_eq!(source.fingerprint().await, fingerprint);
+
+        // Files in subfolders count too
+        std::fs::write(data_path.join("humor/puns"), "A pun\n%\n").unwrap();
+        let added = source.fingerprint().await;
+        assert_ne!(added, fingerprint);
+        std::fs::write(data_path.join("humor/puns"), "A longer pun\n%\n").unwrap();
+        let changed = source.fingerprint().await;
+        assert_ne!(changed, added);
+        std::fs::remove_file(data_path.join("humor/puns")).unwrap();
+        assert_ne!(source.fingerprint().await, changed);
+    }
+    #[tokio::test]
+    async fn text_files_without_dat_files_are_indexed_unless_strict() {
+        let data_path = temp_folder("unindexed");
+        let text = "Kirk\n%\nSpock\n%\n";
+        std::fs::write(data_path.join("startrek"), text).unwrap();
+        let data_file = DataFile::parse(text.as_bytes(), None, None);
+        std::fs::write(data_path.join("indexed"), text).unwrap();
+        std::fs::write(
+            data_path.join("indexed.dat"),
+            data_file.to_bytes().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        // Files without separator lines aren't fortunes
+        std::fs::write(data_path.join("README"), "A folder of fortunes\n").unwrap();
+        std::fs::write(data_path.join("Makefile"), "all:\n\tstrfile startrek\n").unwrap();
+        let source = DirectorySource::new(vec![data_path.clone()]).unwrap();
+        let mut options = FortuneOptions::default();
+        let listing = |fortune_files: Vec<FortuneFileInfo>| {
+            fortune_files
+                .iter()
+                .map(|fortune_file_info| {
+                    (
+                        fortune_file_info.fortune_file(),
+                        fortune_file_info.is_indexed(),
+                        fortune_file_info.count(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            listing(source.fortune_files(&options).await.unwrap()),
+            [
+                ("indexed".to_owned(), true, 2),
+                ("startrek".to_owned(), false, 2)
+            ]
+        );
+        let corpus = Corpus::load_source(Arc::new(source), &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            corpus.fortune_by_id("startrek/1").await.unwrap().fortune(),
+            "Spock\n"
+        );
+
+        options.set_strict(true);
+        let source = DirectorySource::new(vec![data_path]).unwrap();
+        assert_eq!(
+            listing(source.fortune_files(&options).await.unwrap()),
+            [("indexed".to_owned(), true, 2)]
+        );
+    }
+    #[tokio::test]
+    async fn earlier_folders_win_over_later_ones() {
+        let first = temp_folder("first");
+        let second = temp_folder("second");
+        for data_path in [&first, &second] {
+            std::fs::create_dir_all(data_path.join("humor")).unwrap();
+        }
+        std::fs::write(first.join("startrek"), "Kirk\n%\n").unwrap();
+        std::fs::write(first.join("humor/puns"), "First pun\n%\n").unwrap();
+        std::fs::write(second.join("startrek"), "Picard\n%\nRiker\n%\n").unwrap();
+        std::fs::write(second.join("humor/puns"), "Second pun\n%\n").unwrap();
+        std::fs::write(second.join("zippy"), "Yow!\n%\n").unwrap();
+        let missing = first.join("missing");
+        let corpus = Corpus::load_paths(&[missing, first, second], &FortuneOptions::default())
+            .await
+            .unwrap();
+        let ids: Vec<String> = corpus
+            .fortune_files(Collection::All)
+            .iter()
+            .map(|fortune_file_info| fortune_file_info.collection_id())
+            .collect();
+        assert_eq!(ids, ["humor/puns", "startrek", "zippy"]);
+        for (id, fortune) in [
+            ("startrek/0", "Kirk\n"),
+            ("humor/puns/0", "First pun\n"),
+            ("zippy/0", "Yow!\n"),
+        ] {
+            assert_eq!(corpus.fortune_by_id(id).await.unwrap().fortune(), fortune);
+        }
+        assert!(corpus.fortune_by_id("startrek/1").await.is_err());
+    }
+    #[tokio::test]
+    async fn corpus_is_stale_when_a_file_changes() {
+        let data_path = temp_folder("stale");
+        std::fs::write(data_path.join("startrek"), "Kirk\n%\n").unwrap();
+        let options = FortuneOptions::default();
+        let load = || Corpus::load_with(&data_path, &options);
+        let corpus = load().await.unwrap();
+        assert!(!corpus.is_stale().await);
+
+        std::fs::write(data_path.join("zippy"), "Yow!\n%\n").unwrap();
+        assert!(corpus.is_stale().await);
+        let corpus = load().await.unwrap();
+        assert!(!corpus.is_stale().await);
+
+        std::fs::write(data_path.join("zippy"), "Yow! Are we having fun yet?\n%\n").unwrap();
+        assert!(corpus.is_stale().await);
+        let corpus = load().await.unwrap();
+        assert!(!corpus.is_stale().await);
+
+        std::fs::remove_file(data_path.join("zippy")).unwrap();
+        assert!(corpus.is_stale().await);
+    }
+    #[test]
+    fn new_needs_a_data_folder() {
+        assert!(matches!(
+            DirectorySource::new(vec![]),
+            Err(FortuneError::DataFolder { .. })
+        ));
+        assert!(DirectorySource::new(vec![PathBuf::from("data")]).is_ok());
+    }
+}
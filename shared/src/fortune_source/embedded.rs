@@ -0,0 +1,69 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use super::super::{FortuneFileInfo, FortuneOptions, Result};
+use super::archive::read_archive;
+use super::FortuneSource;
+
+/// The archive of the data folder that was built into the library.
+static ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/embedded.tar"));
+
+/// The fortune files that were built into the library with the `embedded` feature, from the
+/// data folder named by the `FORTUNE_EMBED_DIR` environment variable when it was built. They
+/// never change, so a corpus loaded from them is never stale.
+pub struct EmbeddedSource {
+    path: PathBuf,
+}
+impl EmbeddedSource {
+    pub fn new() -> Self {
+        Self {
+            path: PathBuf::from("<embedded>"),
+        }
+    }
+}
+impl Default for EmbeddedSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[async_trait]
+impl FortuneSource for EmbeddedSource {
+    fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>> {
+        read_archive(&self.path, ARCHIVE)?
+            .fortune_files(options)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_is_built_in() {
+        // The build always makes an archive, even an empty one if there's no folder to embed
+        assert!(!ARCHIVE.is_empty());
+        assert!(read_archive(&PathBuf::from("<embedded>"), ARCHIVE).is_ok());
+    }
+    #[tokio::test]
+    async fn embedded_files_are_listed() {
+        let source = EmbeddedSource::new();
+        assert_eq!(source.path(), &PathBuf::from("<embedded>"));
+        let fortune_files = source
+            .fortune_files(&FortuneOptions::default())
+            .await
+            .unwrap();
+        // The variable is read when the library is built, not when the test runs
+        if option_env!("FORTUNE_EMBED_DIR").is_none() {
+            assert!(fortune_files.is_empty());
+        }
+    }
+}
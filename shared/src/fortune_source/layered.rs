@@ -0,0 +1,176 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use super::super::{FortuneError, FortuneFileInfo, FortuneOptions, Result, WordIndex};
+use super::FortuneSource;
+
+/// Sources stacked on top of each other, e.g. a data folder over the fortunes built into the
+/// program. A fortune file in an earlier source hides any file with the same
+/// [collection id](FortuneFileInfo::collection_id) in a later one, so the earlier sources can
+/// override the later ones as well as extend them.
+pub struct LayeredSource {
+    sources: Vec<Arc<dyn FortuneSource>>,
+    /// The source that each fortune file came from, by its path
+    layers: Mutex<HashMap<PathBuf, usize>>,
+}
+impl LayeredSource {
+    /// Creates a source from the given sources, in order. There must be at least one.
+    pub fn new(sources: Vec<Arc<dyn FortuneSource>>) -> Result<Self> {
+        if sources.is_empty() {
+            return Err(FortuneError::DataFolder {
+                path: PathBuf::new(),
+                source: std::io::Error::new(ErrorKind::NotFound, "No sources were given"),
+            });
+        }
+        Ok(Self {
+            sources,
+            layers: Mutex::new(HashMap::new()),
+        })
+    }
+    pub fn sources(&self) -> &[Arc<dyn FortuneSource>] {
+        &self.sources
+    }
+    /// Returns the source that the fortune file came from.
+    fn layer(&self, fortune_file_info: &FortuneFileInfo) -> &dyn FortuneSource {
+        let layer = self
+            .layers
+            .lock()
+            .unwrap()
+            .get(fortune_file_info.path())
+            .copied()
+            .unwrap_or_default();
+        self.sources[layer].as_ref()
+    }
+}
+#[async_trait]
+impl FortuneSource for LayeredSource {
+    /// Returns the first source's path.
+    fn path(&self) -> &PathBuf {
+        self.sources[0].path()
+    }
+    /// Lists the fortune files in all the sources. Sources whose data folders don't exist
+    /// are skipped, but at least one must.
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>> {
+        let mut fortune_files: Vec<FortuneFileInfo> = vec![];
+        let mut missing = None;
+        let mut found = false;
+        for (layer, source) in self.sources.iter().enumerate() {
+            let source_files = match source.fortune_files(options).await {
+                Ok(source_files) => source_files,
+                Err(FortuneError::DataFolder {
+                    path,
+                    source: error,
+                }) if error.kind() == ErrorKind::NotFound => {
+                    missing.get_or_insert(FortuneError::DataFolder {
+                        path,
+                        source: error,
+                    });
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            found = true;
+            let mut layers = self.layers.lock().unwrap();
+            for fortune_file_info in source_files {
+                let collection_id = fortune_file_info.collection_id();
+                if fortune_files
+                    .iter()
+                    .any(|fortune_file| fortune_file.collection_id() == collection_id)
+                {
+                    continue;
+                }
+                layers.insert(fortune_file_info.path().to_owned(), layer);
+                fortune_files.push(fortune_file_info);
+            }
+        }
+        match missing {
+            Some(error) if !found => Err(error),
+            _ => Ok(fortune_files),
+        }
+    }
+    async fn fortune_bytes<'a>(
+        &'a self,
+        fortune_file_info: &'a FortuneFileInfo,
+        index: usize,
+    ) -> Result<Cow<'a, [u8]>> {
+        self.layer(fortune_file_info)
+            .fortune_bytes(fortune_file_info, index)
+            .await
+    }
+    async fn text<'a>(&'a self, fortune_file_info: &'a FortuneFileInfo) -> Result<Cow<'a, [u8]>> {
+        self.layer(fortune_file_info).text(fortune_file_info).await
+    }
+    async fn word_index(&self, fortune_file_info: &FortuneFileInfo) -> Option<WordIndex> {
+        self.layer(fortune_file_info)
+            .word_index(fortune_file_info)
+            .await
+    }
+    /// Combines the sources' fingerprints.
+    async fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for source in &self.sources {
+            source.fingerprint().await.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{Corpus, MemorySource};
+
+    /// Returns a source with the given path that holds the given fortune text files.
+    fn memory_source(path: &str, files: &[(&str, &str)]) -> Arc<dyn FortuneSource> {
+        let mut source = MemorySource::with_path(PathBuf::from(path));
+        for (path, contents) in files {
+            source.insert(Path::new(path), contents.as_bytes().to_vec());
+        }
+        Arc::new(source)
+    }
+
+    #[tokio::test]
+    async fn earlier_layers_win() {
+        let source = LayeredSource::new(vec![
+            memory_source("personal", &[("startrek", "Kirk\n%\n")]),
+            memory_source(
+                "system",
+                &[
+                    ("startrek", "Picard\n%\nRiker\n%\n"),
+                    ("zippy", "Yow!\n%\n"),
+                ],
+            ),
+        ])
+        .unwrap();
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(corpus.data_path(), &PathBuf::from("personal"));
+        let startrek = corpus.fortune_by_id("startrek/0").await.unwrap();
+        assert_eq!(startrek.fortune(), "Kirk\n");
+        assert!(corpus.fortune_by_id("startrek/1").await.is_err());
+        // The later layer still adds the files that the earlier one doesn't have
+        let zippy = corpus.fortune_by_id("zippy/0").await.unwrap();
+        assert_eq!(zippy.fortune(), "Yow!\n");
+    }
+    #[test]
+    fn new_needs_a_source() {
+        assert!(matches!(
+            LayeredSource::new(vec![]),
+            Err(FortuneError::DataFolder { .. })
+        ));
+    }
+}
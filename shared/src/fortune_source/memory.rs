@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::super::compression::compressed_path;
+use super::super::listing::{self, Listed};
+use super::super::{
+    manifest, parse_data_file, text_encoding, word_index, Compression, DataFile, FortuneError,
+    FortuneFileInfo, FortuneOptions, LoadedText, Result, WordIndex,
+};
+use super::FortuneSource;
+
+/// A fortune file's text, and how it was compressed, if it was.
+type Text = (Arc<[u8]>, Option<Compression>);
+
+/// Fortune files held in memory, laid out as they would be in a data folder: each file has a
+/// path relative to the data folder, e.g. `humor/puns.dat`, and the same companions, such as
+/// `.encoding` and `.toml` files, that it could have on disk.
+pub struct MemorySource {
+    path: PathBuf,
+    files: BTreeMap<PathBuf, Arc<[u8]>>,
+}
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::with_path(PathBuf::from("<memory>"))
+    }
+    /// Creates an empty source whose files' paths, in errors, are relative to the given path.
+    pub(crate) fn with_path(path: PathBuf) -> Self {
+        Self {
+            path,
+            files: BTreeMap::new(),
+        }
+    }
+    /// Adds a file with the given path, relative to the data folder, replacing any file that
+    /// is already there.
+    pub fn insert(&mut self, path: &Path, contents: impl Into<Arc<[u8]>>) -> &mut Self {
+        // Archives often name their files `./startrek` and the like
+        let path = path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        self.files.insert(path, contents.into());
+        self
+    }
+    /// Returns the text of the given text file, and how it was compressed. If there is a
+    /// compressed text file instead of it, it is decompressed.
+    fn text(&self, text_path: &Path) -> Result<Option<Text>> {
+        if let Some(text) = self.files.get(text_path) {
+            return Ok(Some((text.clone(), None)));
+        }
+        for compression in Compression::ALL {
+            let path = compressed_path(text_path, compression);
+            if let Some(bytes) = self.files.get(&path) {
+                let text = compression
+                    .decompress(bytes)
+                    .map_err(|error| FortuneError::io(&self.path.join(path), error))?;
+                return Ok(Some((text.into(), Some(compression))));
+            }
+        }
+        Ok(None)
+    }
+    /// Returns the fortune file with the given `.dat` file or, if it has none, text file, and
+    /// the given text.
+    fn fortune_file_info(
+        &self,
+        path: &Path,
+        data_file: DataFile,
+        indexed: bool,
+        text: Option<Text>,
+    ) -> Result<FortuneFileInfo> {
+        let text_path = path.with_extension("");
+        let (offensive, category) = listing::placement(path);
+        let mut fortune_file_info = FortuneFileInfo::new(
+            self.path.join(path.with_extension("dat")),
+            data_file,
+            offensive,
+        );
+        fortune_file_info
+            .set_indexed(indexed)
+            .set_category(category);
+
+        let encoding_path = text_encoding::encoding_path(&text_path);
+        if let Some(label) = self.files.get(&encoding_path) {
+            let encoding = text_encoding::parse_declaration(
+                &self.path.join(encoding_path),
+                &String::from_utf8_lossy(label),
+            )?;
+            fortune_file_info.set_encoding(Some(encoding));
+        }
+        let manifest_path = manifest::manifest_path(&text_path);
+        if let Some(manifest) = self.files.get(&manifest_path) {
+            let manifest = manifest::parse_manifest(
+                &self.path.join(manifest_path),
+                &String::from_utf8_lossy(manifest),
+            )?;
+            fortune_file_info.set_manifest(Some(manifest));
+        }
+        let word_index = self
+            .files
+            .get(&word_index::word_index_path(path))
+            .and_then(|bytes| WordIndex::from_bytes(bytes))
+            .filter(|word_index| word_index.count() == fortune_file_info.count());
+        fortune_file_info.set_word_index(word_index);
+        // A missing text file is reported when a fortune is read from it
+        if let Some((text, compression)) = text {
+            fortune_file_info
+                .set_compression(compression)
+                .set_loaded_text(Some(LoadedText::Shared(text)));
+        }
+        Ok(fortune_file_info)
+    }
+}
+impl Default for MemorySource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[async_trait]
+impl FortuneSource for MemorySource {
+    fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    /// Lists the fortune files the way a data folder's are [listed](listing::list).
+    async fn fortune_files(&self, options: &FortuneOptions) -> Result<Vec<FortuneFileInfo>> {
+        let paths: Vec<&PathBuf> = self.files.keys().collect();
+        let mut data_files: Vec<FortuneFileInfo> = vec![];
+        for listed in listing::list(&paths, options.strict()) {
+            let fortune_file_info = match listed {
+                Listed::Indexed(path) => {
+                    let data_file = parse_data_file(&self.path.join(&path), &self.files[&path])?;
+                    let text = self.text(&path.with_extension(""))?;
+                    self.fortune_file_info(&path, data_file, true, text)?
+                }
+                Listed::Unindexed(path, compression) => {
+                    let bytes = &self.files[&path];
+                    let text: Arc<[u8]> = match compression {
+                        Some(compression) => compression
+                            .decompress(bytes)
+                            .map_err(|error| FortuneError::io(&self.path.join(&path), error))?
+                            .into(),
+                        None => bytes.clone(),
+                    };
+                    if !listing::is_fortune_text(&text) {
+                        continue;
+                    }
+                    let data_file = DataFile::parse(&text, None, None);
+                    self.fortune_file_info(&path, data_file, false, Some((text, compression)))?
+                }
+            };
+            data_files.push(fortune_file_info);
+        }
+        let mut fortune_files: Vec<FortuneFileInfo> = vec![];
+        listing::merge(&mut fortune_files, data_files);
+        Ok(fortune_files)
+    }
+}
@@ -4,19 +4,56 @@
  */
 use serde::Serialize;
 
+use super::Manifest;
+
 #[derive(Serialize)]
 pub struct FortuneStats {
     file: String,
+    category: Option<String>,
     count: usize,
+    offensive: bool,
+    percent: f64,
+    #[serde(flatten)]
+    manifest: Manifest,
 }
 impl FortuneStats {
-    pub fn new(file: String, count: usize) -> Self {
-        Self { file, count }
+    pub fn new(file: String, category: Option<String>, count: usize, offensive: bool) -> Self {
+        Self {
+            file,
+            category,
+            count,
+            offensive,
+            percent: 0.0,
+            manifest: Manifest::default(),
+        }
     }
     pub fn file(&self) -> String {
         self.file.to_owned()
     }
+    /// The subfolder of the data folder that the file is in, e.g. `humor`
+    pub fn category(&self) -> Option<String> {
+        self.category.to_owned()
+    }
     pub fn count(&self) -> usize {
         self.count
     }
+    pub fn is_offensive(&self) -> bool {
+        self.offensive
+    }
+    /// The percent chance of a fortune being chosen from the file
+    pub fn percent(&self) -> f64 {
+        self.percent
+    }
+    pub fn set_percent(&mut self, percent: f64) -> &mut Self {
+        self.percent = percent;
+        self
+    }
+    /// The metadata from the file's manifest, which is empty if it has none
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+    pub fn set_manifest(&mut self, manifest: Option<Manifest>) -> &mut Self {
+        self.manifest = manifest.unwrap_or_default();
+        self
+    }
 }
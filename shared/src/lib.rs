@@ -2,142 +2,553 @@
  * Copyright (c) 2024 Paul Sobolik
  * Created 2024-05-02
  */
-use std::io;
-use std::io::{Error, ErrorKind, SeekFrom};
-use std::path::PathBuf;
+use std::io::{ErrorKind, SeekFrom};
+use std::path::{Path, PathBuf};
 
 use rand::Rng;
 use tokio::fs;
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt, Result};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-pub use data_file::{DataFile, Flags, Header};
+pub use chrono::NaiveDate;
+pub use compression::Compression;
+pub use corpus::Corpus;
+pub use cursors::Cursors;
+pub use data_file::{DataFile, Flags, Header, Separator};
+pub use encoding_rs::Encoding;
+pub use formatting::FormatOptions;
 pub use fortune::Fortune;
+pub use fortune_error::{FortuneError, Result};
 pub use fortune_file_info::FortuneFileInfo;
+pub use fortune_options::{Collection, FortuneOptions, Selection};
+#[cfg(feature = "embedded")]
+pub use fortune_source::EmbeddedSource;
+pub use fortune_source::{
+    source_for_paths, ArchiveSource, DirectorySource, FortuneSource, LayeredSource, MemorySource,
+};
 pub use fortune_stats::FortuneStats;
+pub use manifest::Manifest;
+pub use query::Query;
+pub use rot13::rot13;
+pub use search::{Search, SearchOptions};
+pub use source::Source;
+pub use text_encoding::Decoding;
+pub use validate::{validate, validate_data_file, Issue, Problem};
+pub use word_index::{words, WordIndex};
 
+pub mod compression;
+pub mod corpus;
+pub mod cursors;
 pub mod data_file;
+pub mod formatting;
 pub mod fortune;
+pub mod fortune_error;
 pub mod fortune_file_info;
+pub mod fortune_options;
+pub mod fortune_source;
 pub mod fortune_stats;
+mod listing;
+pub mod manifest;
+#[cfg(feature = "mmap")]
+mod mapped;
+pub mod query;
+pub mod rot13;
+pub mod search;
+pub mod source;
+pub mod text_encoding;
+pub mod validate;
+pub mod word_index;
+
+use fortune_file_info::LoadedText;
+use listing::Listed;
 
 /// Returns a fortune chosen randomly from all the fortune files in the given path.
 pub async fn random_fortune(data_path: &PathBuf) -> Result<Fortune> {
-    let fortune_files = fortune_files(data_path).await?;
-
-    // Select a random index between 0 and the total number of fortunes
-    let total = fortune_files.iter().fold(0, |total, fortune_file_info| {
-        total + fortune_file_info.count()
-    });
-    let mut index = rand::thread_rng().gen_range(0..total);
+    random_fortune_with(data_path, &FortuneOptions::default()).await
+}
+/// Returns a fortune chosen randomly from the fortune files in the given path,
+/// as directed by the given [FortuneOptions].
+pub async fn random_fortune_with(data_path: &PathBuf, options: &FortuneOptions) -> Result<Fortune> {
+    Corpus::load_with(data_path, options)
+        .await?
+        .random_fortune(options)
+        .await
+}
+/// Returns the fortune with the given identifier, e.g. `startrek/12`, from the fortune files
+/// in the given path, including those without a `.dat` file.
+pub async fn fortune_by_id(data_path: &PathBuf, id: &str) -> Result<Fortune> {
+    Corpus::load(data_path).await?.fortune_by_id(id).await
+}
+/// Returns an array of [FortuneStats], with an entry for each of the fortune files in the given path.
+pub async fn fortune_stats(data_path: &PathBuf) -> Result<Vec<FortuneStats>> {
+    fortune_stats_with(data_path, &FortuneOptions::default()).await
+}
+/// Returns an array of [FortuneStats], with an entry for each of the fortune files in the given path
+/// that are in the collection given by the [FortuneOptions].
+pub async fn fortune_stats_with(
+    data_path: &PathBuf,
+    options: &FortuneOptions,
+) -> Result<Vec<FortuneStats>> {
+    Corpus::load_with(data_path, options)
+        .await?
+        .fortune_stats(options)
+}
+/// The environment variable that holds a list of data folders, separated the way `PATH` is.
+pub const FORTUNE_PATH: &str = "FORTUNE_PATH";
 
-    // Find the fortune file that contains the selected index, and calculate the index of the
-    // fortune's offset in that file
-    let fortune_file_info = fortune_files.iter().find(|fortune_file_info| {
-        let count = fortune_file_info.count();
-        if count < index {
-            index -= count;
-            false
-        } else {
-            true
-        }
-    });
-    // Return the text between the offsets in the fortune file.
-    if let Some(fortune_file_info) = fortune_file_info {
-        let start = fortune_file_info.offset_at(index) as u64;
-        let end = fortune_file_info.offset_at(index + 1) as u64;
-        let fortune = get_fortune(&fortune_file_info.path.with_extension(""), start, end).await?;
-        Ok(Fortune::new(fortune_file_info.fortune_file(), fortune))
+/// Returns the data folders listed in the `FORTUNE_PATH` environment variable, in order,
+/// or `None` if it isn't set or is empty.
+pub fn fortune_path() -> Option<Vec<PathBuf>> {
+    let value = std::env::var_os(FORTUNE_PATH)?;
+    let data_paths: Vec<PathBuf> = std::env::split_paths(&value)
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect();
+    if data_paths.is_empty() {
+        None
     } else {
-        Err(Error::new(ErrorKind::Other, "This should never happen"))
+        Some(data_paths)
     }
 }
-/// Returns an array of [FortuneStats], with an entry for each of the fortune files in the given path.
-pub async fn fortune_stats(data_path: &PathBuf) -> Result<Vec<FortuneStats>> {
-    match fortune_files(data_path).await {
-        Ok(fortune_file_infos) => {
-            let fortune_stats = fortune_file_infos
-                .iter()
-                .map(|fortune_file_info| {
-                    FortuneStats::new(fortune_file_info.fortune_file(), fortune_file_info.count())
-                })
-                .collect();
-            Ok(fortune_stats)
-        }
-        Err(error) => {
-            // Enhance error message
-            let message = format!(
-                "Cannot read data folder '{}': {}",
-                data_path.display(),
-                error
-            );
-            let error = io::Error::new(error.kind(), message);
-            Err(error)
-        }
+/// The subfolder of the data folder that holds the offensive fortune files.
+const OFFENSIVE_FOLDER: &str = "off";
+
+/// Returns the fortune files in the given paths and their subfolders, both offensive and not.
+/// Unless `strict` is true, text files that don't have a `.dat` file are included, and indexed
+/// in memory. Paths that don't exist are skipped, but at least one must. If more
+/// than one path has a file with the same [collection id](FortuneFileInfo::collection_id),
+/// the one in the earlier path is used, and the others are ignored.
+async fn fortune_files(
+    data_paths: &[PathBuf],
+    options: &FortuneOptions,
+) -> Result<Vec<FortuneFileInfo>> {
+    let existing_paths: Vec<&PathBuf> = data_paths.iter().filter(|path| path.is_dir()).collect();
+    if existing_paths.is_empty() {
+        return Err(FortuneError::DataFolder {
+            path: data_paths.first().cloned().unwrap_or_default(),
+            source: std::io::Error::from(ErrorKind::NotFound),
+        });
+    }
+    let mut data_files: Vec<FortuneFileInfo> = vec![];
+    for data_path in existing_paths {
+        listing::merge(
+            &mut data_files,
+            folder_fortune_files(data_path, options).await?,
+        );
     }
+    Ok(data_files)
 }
-async fn fortune_files(data_path: &PathBuf) -> Result<Vec<FortuneFileInfo>> {
-    match fs::read_dir(data_path).await {
-        Ok(mut entries) => {
-            let mut data_files: Vec<FortuneFileInfo> = vec![];
-            while let Some(entry) = entries.next_entry().await? {
-                let entry = entry.path();
-                if !entry.is_file() {
+/// Returns the fortune files in the given folder and its subfolders, as they are
+/// [listed](listing::list). The files in the offensive folder are offensive, and each
+/// subfolder's path, relative to the given folder or the offensive folder, is the category of
+/// the files in it.
+async fn folder_fortune_files(
+    data_path: &Path,
+    options: &FortuneOptions,
+) -> Result<Vec<FortuneFileInfo>> {
+    let mut data_files: Vec<FortuneFileInfo> = vec![];
+    let paths = listing::walk(data_path).await?;
+    for listed in listing::list(&paths, options.strict()) {
+        let mut fortune_file_info = match listed {
+            Listed::Indexed(path) => {
+                let entry = data_path.join(&path);
+                // The offsets are copied out of a `.dat` file, so it is read, not mapped
+                let data_file = read_data_file(&entry).await?;
+                let (offensive, category) = listing::placement(&path);
+                let mut fortune_file_info = FortuneFileInfo::new(entry, data_file, offensive);
+                let compression = compression::find_compression(&fortune_file_info.text_path());
+                fortune_file_info
+                    .set_category(category)
+                    .set_compression(compression);
+                fortune_file_info
+            }
+            Listed::Unindexed(path, compression) => {
+                let entry = data_path.join(&path);
+                let text = compression::read_text(&entry)
+                    .await
+                    .map_err(|error| text_error(&entry, error))?;
+                if !listing::is_fortune_text(&text) {
                     continue;
                 }
-                if let Some(extension) = entry.extension() {
-                    if extension != "dat" {
-                        continue;
-                    }
-                    if let Some(data_file) = read_data_file(&entry).await? {
-                        data_files.push(FortuneFileInfo::new(entry, data_file));
-                    }
+                let data_file = DataFile::parse(&text, None, None);
+                let (offensive, category) = listing::placement(&path);
+                let mut fortune_file_info =
+                    FortuneFileInfo::new(entry.with_extension("dat"), data_file, offensive);
+                fortune_file_info
+                    .set_indexed(false)
+                    .set_category(category)
+                    .set_compression(compression);
+                // A compressed text file is decompressed to index it, so its text is kept
+                if compression.is_some() {
+                    fortune_file_info.set_loaded_text(Some(LoadedText::Shared(text.into())));
                 }
+                fortune_file_info
             }
-            Ok(data_files)
+        };
+        // A manifest can make a file outside the offensive folder offensive
+        let text_path = fortune_file_info.text_path();
+        let encoding = text_encoding::declared_encoding(&text_path).await?;
+        let manifest = manifest::read_manifest(&text_path).await?;
+        fortune_file_info
+            .set_encoding(encoding)
+            .set_manifest(manifest);
+        #[cfg(feature = "mmap")]
+        if options.mapped() && fortune_file_info.compression().is_none() {
+            // A missing text file is reported when a fortune is read from it
+            let text = mapped::map(&fortune_file_info.text_path()).ok();
+            fortune_file_info.set_loaded_text(text.map(LoadedText::Mapped));
         }
-        Err(error) => {
-            // Enhance error message
-            let message = format!(
-                "Cannot read data folder '{}': {}",
-                data_path.display(),
-                error
-            );
-            let error = io::Error::new(error.kind(), message);
-            Err(error)
+        data_files.push(fortune_file_info);
+    }
+    Ok(data_files)
+}
+/// A fortune file, and the indexes of the fortunes in it that can be chosen.
+struct Candidates<'a> {
+    fortune_file_info: &'a FortuneFileInfo,
+    /// `None` if every fortune in the file can be chosen
+    indexes: Option<Vec<usize>>,
+}
+impl<'a> Candidates<'a> {
+    /// Finds the fortunes in the file whose lengths are within the options' limits.
+    fn new(fortune_file_info: &'a FortuneFileInfo, options: &FortuneOptions) -> Self {
+        if options.min_length().is_none() && options.max_length().is_none() {
+            return Self {
+                fortune_file_info,
+                indexes: None,
+            };
+        }
+        let min_length = options.min_length().unwrap_or(0);
+        let max_length = options.max_length().unwrap_or(usize::MAX);
+        let indexes = (0..fortune_file_info.count())
+            .filter(|index| {
+                fortune_file_info
+                    .length_at(*index)
+                    .is_some_and(|length| (min_length..=max_length).contains(&length))
+            })
+            .collect();
+        Self {
+            fortune_file_info,
+            indexes: Some(indexes),
+        }
+    }
+    fn count(&self) -> usize {
+        match &self.indexes {
+            Some(indexes) => indexes.len(),
+            None => self.fortune_file_info.count(),
         }
     }
+    /// Returns the index in the file of the nth candidate.
+    fn index(&self, nth: usize) -> usize {
+        match &self.indexes {
+            Some(indexes) => indexes[nth],
+            None => nth,
+        }
+    }
+    fn choice(&self, nth: usize) -> (&'a FortuneFileInfo, usize) {
+        (self.fortune_file_info, self.index(nth))
+    }
+}
+/// Returns the probability of choosing each of the fortune files, given the options' sources.
+/// A source may name a file or a category; a file belongs to the first source that names it.
+/// Each source with a percentage gets that share, split among its files. The share that is
+/// left over is split among the sources without a percentage, or, if there are none, among
+/// the files that aren't listed at all. Shares are split in proportion to the files' counts,
+/// or equally if the options' selection is [Selection::Equal], times the files'
+/// [weights](FortuneFileInfo::weight). A file that a source names is asked for, so its weight
+/// is ignored, and so are the weights of a share's files if they are all 0.
+fn source_weights(
+    data_path: &PathBuf,
+    candidates: &[Candidates],
+    options: &FortuneOptions,
+) -> Result<Vec<f64>> {
+    let sources = options.sources();
+    for source in sources {
+        let name = source.name();
+        if !candidates
+            .iter()
+            .any(|candidates| candidates.fortune_file_info.matches(&name))
+        {
+            return Err(FortuneError::UnknownSource {
+                path: data_path.to_owned(),
+                name,
+            });
+        }
+    }
+    let total = sources.iter().filter_map(Source::percent).sum::<f64>();
+    if total > 100.0 {
+        return Err(FortuneError::PercentOver {
+            path: data_path.to_owned(),
+            total,
+        });
+    }
+
+    let source_of = |candidates: &Candidates| {
+        sources
+            .iter()
+            .position(|source| candidates.fortune_file_info.matches(&source.name()))
+    };
+    let unweighted_share = |candidates: &Candidates| match options.selection() {
+        _ if candidates.count() == 0 => 0.0,
+        Selection::Weighted => candidates.count() as f64,
+        Selection::Equal => 1.0,
+    };
+    let share = |candidates: &Candidates| {
+        let named = source_of(candidates)
+            .is_some_and(|nth| candidates.fortune_file_info.is_named(&sources[nth].name()));
+        if named {
+            unweighted_share(candidates)
+        } else {
+            unweighted_share(candidates) * candidates.fortune_file_info.weight()
+        }
+    };
+    // The total shares, weighted and unweighted, of the files that pass the filter
+    let total_shares = |filter: &dyn Fn(&Candidates) -> bool| {
+        candidates
+            .iter()
+            .filter(|candidates| filter(candidates))
+            .fold((0.0, 0.0), |(shares, unweighted_shares), candidates| {
+                (
+                    shares + share(candidates),
+                    unweighted_shares + unweighted_share(candidates),
+                )
+            })
+    };
+    let source_shares: Vec<(f64, f64)> = (0..sources.len())
+        .map(|nth| total_shares(&|candidates| source_of(candidates) == Some(nth)))
+        .collect();
+    let any_unweighted = sources.iter().any(|source| source.percent().is_none());
+    // The files that split the left over share
+    let in_pool = |candidates: &Candidates| match source_of(candidates) {
+        Some(nth) => sources[nth].percent().is_none(),
+        None => !any_unweighted,
+    };
+    let pool_shares = total_shares(&in_pool);
+    // A file's part of a share, among files with the given total shares
+    let part = |candidates: &Candidates, (shares, unweighted_shares): (f64, f64)| {
+        if shares > 0.0 {
+            share(candidates) / shares
+        } else if unweighted_shares > 0.0 {
+            unweighted_share(candidates) / unweighted_shares
+        } else {
+            0.0
+        }
+    };
+    let weights = candidates
+        .iter()
+        .map(|candidates| {
+            let percent = source_of(candidates)
+                .and_then(|nth| sources[nth].percent().map(|percent| (nth, percent)));
+            match percent {
+                Some((nth, percent)) => percent * part(candidates, source_shares[nth]),
+                None if in_pool(candidates) => (100.0 - total) * part(candidates, pool_shares),
+                None => 0.0,
+            }
+        })
+        .collect();
+    Ok(weights)
+}
+/// Chooses a file in proportion to the given weights, and then a fortune in that file.
+/// Returns the file and the index of the fortune in it.
+fn choose_by_weight<'a, R: Rng + ?Sized>(
+    candidates: &[Candidates<'a>],
+    weights: &[f64],
+    rng: &mut R,
+) -> Option<(&'a FortuneFileInfo, usize)> {
+    let total = weights.iter().sum::<f64>();
+    if !total.is_finite() || total <= 0.0 {
+        return None;
+    }
+    let mut choice = rng.gen_range(0.0..total);
+    let (candidates, _) = candidates
+        .iter()
+        .zip(weights)
+        .filter(|(_, weight)| **weight > 0.0)
+        .find(|(_, weight)| {
+            if **weight <= choice {
+                choice -= **weight;
+                false
+            } else {
+                true
+            }
+        })
+        // Rounding can leave the choice just past the last weight
+        .or_else(|| {
+            candidates
+                .iter()
+                .zip(weights)
+                .rfind(|(_, weight)| **weight > 0.0)
+        })?;
+    let nth = rng.gen_range(0..candidates.count());
+    Some(candidates.choice(nth))
+}
+/// Chooses a fortune so that every fortune in every file is equally likely.
+/// Returns the file and the index of the fortune in it.
+fn choose_weighted<'a, R: Rng + ?Sized>(
+    candidates: &[Candidates<'a>],
+    rng: &mut R,
+) -> Option<(&'a FortuneFileInfo, usize)> {
+    // Select a random index between 0 and the total number of fortunes
+    let total = candidates
+        .iter()
+        .fold(0, |total, candidates| total + candidates.count());
+    if total == 0 {
+        return None;
+    }
+    let mut nth = rng.gen_range(0..total);
+
+    // Find the fortune file that contains the selected index, and calculate the index of the
+    // fortune's offset in that file
+    candidates
+        .iter()
+        .find(|candidates| {
+            let count = candidates.count();
+            if count <= nth {
+                nth -= count;
+                false
+            } else {
+                true
+            }
+        })
+        .map(|candidates| candidates.choice(nth))
+}
+/// Chooses a file so that every file is equally likely, and then a fortune in that file.
+/// Returns the file and the index of the fortune in it.
+fn choose_equal<'a, R: Rng + ?Sized>(
+    candidates: &[Candidates<'a>],
+    rng: &mut R,
+) -> Option<(&'a FortuneFileInfo, usize)> {
+    let candidates: Vec<&Candidates> = candidates
+        .iter()
+        .filter(|candidates| candidates.count() > 0)
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let candidates = candidates[rng.gen_range(0..candidates.len())];
+    let nth = rng.gen_range(0..candidates.count());
+    Some(candidates.choice(nth))
+}
+/// Returns the fortune at the given index in one of the source's fortune files.
+async fn read_fortune(
+    source: &dyn FortuneSource,
+    fortune_file_info: &FortuneFileInfo,
+    index: usize,
+    decoding: Decoding,
+) -> Result<Fortune> {
+    let bucket = source.fortune_bytes(fortune_file_info, index).await?;
+    decode_fortune(fortune_file_info, index, &bucket, decoding)
+}
+/// Returns the fortune at the given index in a fortune file, given the whole text of the file.
+fn fortune_in_text(
+    fortune_file_info: &FortuneFileInfo,
+    index: usize,
+    text: &[u8],
+    decoding: Decoding,
+) -> Result<Fortune> {
+    let bucket = fortune_slice(fortune_file_info, index, text)?;
+    decode_fortune(fortune_file_info, index, bucket, decoding)
+}
+/// Returns the bytes of the fortune at the given index in a fortune file, given the whole
+/// text of the file.
+fn fortune_slice<'t>(
+    fortune_file_info: &FortuneFileInfo,
+    index: usize,
+    text: &'t [u8],
+) -> Result<&'t [u8]> {
+    let (start, len) = fortune_range(fortune_file_info, index)?;
+    text.get(start as usize..(start + len) as usize)
+        .ok_or_else(|| out_of_range(fortune_file_info, index))
+}
+/// Returns the start and length of the fortune at the given index in a fortune file, which
+/// doesn't include the separator line that follows it.
+fn fortune_range(fortune_file_info: &FortuneFileInfo, index: usize) -> Result<(u64, u64)> {
+    let range = fortune_file_info
+        .data_file
+        .fortune_range(index)
+        .ok_or_else(|| out_of_range(fortune_file_info, index))?;
+    Ok((range.start as u64, range.len() as u64))
+}
+/// Makes a [Fortune] from the bytes of a fortune file's entry, decoding them from the file's
+/// encoding, and from ROT13 if the file is rotated.
+fn decode_fortune(
+    fortune_file_info: &FortuneFileInfo,
+    index: usize,
+    bucket: &[u8],
+    decoding: Decoding,
+) -> Result<Fortune> {
+    let mut rotated;
+    let bucket = if fortune_file_info.data_file.header.is_rotated() {
+        rotated = bucket.to_vec();
+        rot13(&mut rotated);
+        &rotated
+    } else {
+        bucket
+    };
+    let fortune = text_encoding::decode(bucket, fortune_file_info.encoding(), decoding)
+        .ok_or_else(|| FortuneError::InvalidEncoding {
+            path: fortune_file_info.text_path(),
+            index,
+        })?;
+    Ok(Fortune::new(
+        fortune_file_info.fortune_id(index),
+        fortune_file_info.fortune_file(),
+        index,
+        fortune,
+    ))
+}
+fn out_of_range(fortune_file_info: &FortuneFileInfo, index: usize) -> FortuneError {
+    FortuneError::OffsetOutOfRange {
+        path: fortune_file_info.path().to_owned(),
+        index,
+    }
 }
-async fn get_fortune(path: &PathBuf, start: u64, end: u64) -> Result<String> {
-    let len = (end - start - 2) as usize;
-    let mut bucket = vec![0u8; len];
+/// Converts an error reading a fortune file's text to a [FortuneError].
+fn text_error(text_path: &PathBuf, error: std::io::Error) -> FortuneError {
+    match error.kind() {
+        ErrorKind::NotFound => FortuneError::MissingTextFile {
+            path: text_path.to_owned(),
+        },
+        _ => FortuneError::io(text_path, error),
+    }
+}
+async fn get_fortune(path: &PathBuf, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut bucket = vec![0u8; len as usize];
 
     let mut file = File::open(path).await?;
     file.seek(SeekFrom::Start(start)).await?;
     file.read_exact(&mut bucket).await?;
-    Ok(String::from_utf8(bucket).unwrap_or_default())
+    Ok(bucket)
 }
-async fn read_data_file(in_file: &PathBuf) -> Result<Option<DataFile>> {
+async fn read_data_file(in_file: &PathBuf) -> Result<DataFile> {
+    let bytes = fs::read(in_file)
+        .await
+        .map_err(|error| FortuneError::io(in_file, error))?;
+    parse_data_file(in_file, &bytes)
+}
+/// Reads a `.dat` file's header and offsets from its bytes.
+fn parse_data_file(in_file: &PathBuf, bytes: &[u8]) -> Result<DataFile> {
     let mut data_file = DataFile::default();
+    let mut numbers = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    let mut read_u32 = || {
+        numbers.next().ok_or_else(|| FortuneError::TruncatedHeader {
+            path: in_file.to_owned(),
+        })
+    };
 
-    let mut file = File::open(in_file).await?;
-
-    let version = file.read_u32().await?;
+    let version = read_u32()?;
     if version != data_file.header.version() {
-        return Ok(None);
+        return Err(FortuneError::BadVersion {
+            path: in_file.to_owned(),
+            version,
+        });
     }
 
-    let count = file.read_u32().await?;
-    if count == 0 {
-        return Ok(None);
-    }
-
-    let longest = file.read_u32().await?;
-    let shortest = file.read_u32().await?;
-    let bits = file.read_u32().await?;
-    let flags = Flags::from_bits(bits).unwrap_or_default();
-    let bits = file.read_u32().await?;
-    let separator = bits.to_be_bytes()[0] as char;
+    let count = read_u32()?;
+    let longest = read_u32()?;
+    let shortest = read_u32()?;
+    let flags = Flags::from_bits(read_u32()?).unwrap_or_default();
+    let separator = header_separator(read_u32()?);
 
     data_file.header = *data_file
         .header
@@ -147,8 +558,177 @@ async fn read_data_file(in_file: &PathBuf) -> Result<Option<DataFile>> {
         .set_flags(flags)
         .set_separator(separator);
     for _ in 0..=count {
-        let offset = file.read_u32().await?;
-        data_file.offsets.push(offset);
+        data_file.offsets.push(read_u32()?);
+    }
+    if flags.contains(Flags::Separator) {
+        let numbers: Vec<u32> = numbers.collect();
+        (data_file.separator, data_file.separator_lengths) =
+            DataFile::read_separator(&numbers, count).ok_or_else(|| {
+                FortuneError::TruncatedHeader {
+                    path: in_file.to_owned(),
+                }
+            })?;
+    } else {
+        data_file.separator = Separator::from(separator);
+    }
+    Ok(data_file)
+}
+/// Returns the separator character in the last field of a `.dat` file's header. `strfile`
+/// stores it in the first byte, and this library stores its code point, little-endian, so
+/// the two agree for ASCII separators.
+pub(crate) fn header_separator(field: u32) -> char {
+    let bytes = field.to_be_bytes();
+    char::from_u32(u32::from_le_bytes(bytes)).unwrap_or(bytes[0] as char)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::sync::Arc;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::corpus::tests::{choices, corpora, FILES};
+    use super::*;
+
+    /// Returns the weight that each of the corpus's files gets, by its identifier.
+    fn weights(corpus: &Corpus, options: &FortuneOptions) -> BTreeMap<String, f64> {
+        let candidates: Vec<Candidates> = corpus
+            .fortune_files(options.collection())
+            .into_iter()
+            .map(|fortune_file_info| Candidates::new(fortune_file_info, options))
+            .collect();
+        let weights = source_weights(corpus.data_path(), &candidates, options).unwrap();
+        candidates
+            .iter()
+            .zip(weights)
+            .map(|(candidates, weight)| {
+                let weight = (weight * 100.0).round() / 100.0;
+                (candidates.fortune_file_info.collection_id(), weight)
+            })
+            .collect()
+    }
+    fn sources_options(args: &[&str]) -> FortuneOptions {
+        let mut options = FortuneOptions::default();
+        options.set_sources(Source::parse(args).unwrap());
+        options
+    }
+
+    #[tokio::test]
+    async fn percentages_split_the_rest_among_unlisted_files() {
+        for corpus in corpora("unlisted", FILES).await {
+            let weights = weights(&corpus, &sources_options(&["30%", "startrek"]));
+            // The files that aren't listed split the other 70% by their counts, 2 to 1
+            assert_eq!(weights["startrek"], 30.0);
+            assert_eq!(weights["humor/puns"], 46.67);
+            assert_eq!(weights["zippy"], 23.33);
+        }
+    }
+    #[tokio::test]
+    async fn percentages_split_the_rest_among_sources_without_them() {
+        for corpus in corpora("sources", FILES).await {
+            let weights = weights(&corpus, &sources_options(&["40%", "humor", "zippy"]));
+            assert_eq!(weights["humor/puns"], 40.0);
+            assert_eq!(weights["zippy"], 60.0);
+            assert_eq!(weights["startrek"], 0.0);
+        }
+    }
+    #[tokio::test]
+    async fn percentages_over_100_are_an_error() {
+        for corpus in corpora("over-100", FILES).await {
+            let options = sources_options(&["60%", "startrek", "50%", "zippy"]);
+            let mut rng = rand::thread_rng();
+            assert!(matches!(
+                corpus.choose(&options, &mut rng),
+                Err(FortuneError::PercentOver { .. })
+            ));
+        }
+    }
+    #[tokio::test]
+    async fn equal_selection_gives_each_file_the_same_share() {
+        for corpus in corpora("equal", FILES).await {
+            let mut options = FortuneOptions::default();
+            options.set_selection(Selection::Equal);
+            let weights = weights(&corpus, &options);
+            assert_eq!(weights["startrek"], 33.33);
+            assert_eq!(weights["humor/puns"], 33.33);
+            assert_eq!(weights["zippy"], 33.33);
+            // By count, startrek's three fortunes would be chosen half the time
+            let mut rng = ChaCha8Rng::seed_from_u64(2);
+            let mut files: BTreeMap<String, usize> = BTreeMap::new();
+            for _ in 0..3000 {
+                let (fortune_file_info, _) = corpus.choose(&options, &mut rng).unwrap();
+                *files.entry(fortune_file_info.collection_id()).or_default() += 1;
+            }
+            for (file, count) in files {
+                assert!((900..1100).contains(&count), "{} {}", file, count);
+            }
+        }
+    }
+    #[tokio::test]
+    async fn files_named_with_weight_0_can_be_chosen() {
+        let mut files = FILES.to_vec();
+        files.push(("zippy.toml", "weight = 0.0"));
+        for corpus in corpora("weight-0", &files).await {
+            let weights = weights(&corpus, &sources_options(&["30%", "zippy"]));
+            assert_eq!(weights["zippy"], 30.0);
+            let weights = self::weights(&corpus, &sources_options(&["zippy"]));
+            assert_eq!(weights["zippy"], 100.0);
+            // Unless it is asked for, a file with weight 0 is never chosen
+            let chosen = choices(&corpus, &FortuneOptions::default(), 3);
+            assert!(!chosen.iter().any(|id| id.starts_with("zippy/")));
+        }
+    }
+    #[tokio::test]
+    async fn weights_scale_the_shares_that_percentages_leave() {
+        let mut files = FILES.to_vec();
+        files.push(("startrek.toml", "weight = 2.0"));
+        for corpus in corpora("weights", &files).await {
+            // The other 70% is split 3 * 2 to 2 * 1 between the files that aren't listed
+            let weights = weights(&corpus, &sources_options(&["30%", "zippy"]));
+            assert_eq!(weights["zippy"], 30.0);
+            assert_eq!(weights["startrek"], 52.5);
+            assert_eq!(weights["humor/puns"], 17.5);
+            // Without percentages, the weights scale the files' counts
+            let weights = self::weights(&corpus, &FortuneOptions::default());
+            assert_eq!(weights["startrek"], 66.67);
+            assert_eq!(weights["humor/puns"], 22.22);
+            assert_eq!(weights["zippy"], 11.11);
+        }
+    }
+    #[tokio::test]
+    async fn malformed_manifests_fail_the_load() {
+        let mut source = MemorySource::new();
+        source
+            .insert(Path::new("startrek"), b"Kirk\n%\n".to_vec())
+            .insert(Path::new("startrek.toml"), b"weight = heavy".to_vec());
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default()).await;
+        assert!(matches!(corpus, Err(FortuneError::InvalidManifest { .. })));
+    }
+    #[tokio::test]
+    async fn lengths_limit_the_fortunes_chosen() {
+        for corpus in corpora("lengths", FILES).await {
+            // Lengths include the fortune's last newline, but not its separator line
+            let mut options = FortuneOptions::default();
+            options.set_max_length(Some(5));
+            let chosen: BTreeSet<String> = choices(&corpus, &options, 5).into_iter().collect();
+            assert_eq!(
+                chosen,
+                BTreeSet::from(["startrek/0", "zippy/0"].map(str::to_owned))
+            );
+
+            let mut options = FortuneOptions::default();
+            options.set_min_length(Some(20));
+            let chosen: BTreeSet<String> = choices(&corpus, &options, 5).into_iter().collect();
+            assert_eq!(chosen, BTreeSet::from(["humor/puns/1".to_owned()]));
+
+            options.set_max_length(Some(30));
+            let mut rng = rand::thread_rng();
+            assert!(matches!(
+                corpus.choose(&options, &mut rng),
+                Err(FortuneError::EmptyCorpus { .. })
+            ));
+        }
     }
-    Ok(Some(data_file))
 }
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use super::{Compression, FortuneError, FortuneFileInfo, Result, Separator, OFFENSIVE_FOLDER};
+
+/// Returns the paths of the files in the given data folder and its subfolders, including the
+/// offensive folder, relative to the data folder and sorted. Hidden files and folders are
+/// skipped.
+pub(crate) async fn walk(data_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut folders = vec![PathBuf::new()];
+    while let Some(folder) = folders.pop() {
+        let path = data_path.join(&folder);
+        let data_folder_error = |source| FortuneError::DataFolder {
+            path: path.to_owned(),
+            source,
+        };
+        let mut entries = fs::read_dir(&path).await.map_err(data_folder_error)?;
+        while let Some(entry) = entries.next_entry().await.map_err(data_folder_error)? {
+            let file_type = entry.file_type().await.map_err(data_folder_error)?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if file_type.is_dir() {
+                folders.push(folder.join(name));
+            } else if entry.path().is_file() {
+                files.push(folder.join(name));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+/// A fortune file among the files in a data folder.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Listed {
+    /// A `.dat` file
+    Indexed(PathBuf),
+    /// A text file without a `.dat` file, which is indexed as it is loaded, and how it is
+    /// compressed, if it is
+    Unindexed(PathBuf, Option<Compression>),
+}
+/// Picks the fortune files out of the paths of the files in a data folder, relative to it.
+/// Every `.dat` file is one, and, unless `strict` is true, so is every text file without a
+/// `.dat` file. Text files don't have an extension, unless they are compressed, so the
+/// `.encoding`, manifest and word index files that go with fortune files aren't listed.
+/// Hidden files, and the files in hidden folders, are skipped.
+pub(crate) fn list<P: AsRef<Path>>(paths: &[P], strict: bool) -> Vec<Listed> {
+    let all: HashSet<&Path> = paths.iter().map(AsRef::as_ref).collect();
+    paths
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|path| !is_hidden(path))
+        .filter_map(|path| match path.extension() {
+            Some(extension) if extension == "dat" => Some(Listed::Indexed(path.to_owned())),
+            _ if strict || all.contains(path.with_extension("dat").as_path()) => None,
+            None => Some(Listed::Unindexed(path.to_owned(), None)),
+            Some(_) => Compression::from_path(path)
+                .map(|compression| Listed::Unindexed(path.to_owned(), Some(compression))),
+        })
+        .collect()
+}
+/// Returns true if the text of a file that was [listed](list) without a `.dat` file is
+/// fortunes, i.e. it has at least one `%` line. Other files without an extension, like a
+/// README, a LICENSE or a Makefile, aren't fortune files, and are skipped when they are loaded.
+pub(crate) fn is_fortune_text(text: &[u8]) -> bool {
+    Separator::default().is_in(text)
+}
+/// Returns true if the file, or any folder it is in, is hidden.
+fn is_hidden(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+/// Adds the fortune files from a data folder to the ones from earlier data folders, unless one
+/// of those has the same [collection id](FortuneFileInfo::collection_id). The folder's files
+/// are added in a predictable order, so the same random numbers choose the same fortune. The
+/// files outside the offensive folder come first, so they win if a manifest gives one the
+/// same identifier as a file in it.
+pub(crate) fn merge(
+    fortune_files: &mut Vec<FortuneFileInfo>,
+    mut folder_files: Vec<FortuneFileInfo>,
+) {
+    folder_files.sort_by(|a, b| (a.offensive, a.path()).cmp(&(b.offensive, b.path())));
+    for fortune_file_info in folder_files {
+        let collection_id = fortune_file_info.collection_id();
+        if !fortune_files
+            .iter()
+            .any(|fortune_file| fortune_file.collection_id() == collection_id)
+        {
+            fortune_files.push(fortune_file_info);
+        }
+    }
+}
+/// Returns whether the file with the given path, relative to its data folder, is in the
+/// offensive folder, and its category, which is the path of the folder it is in, relative to
+/// the data folder or the offensive folder.
+pub(crate) fn placement(path: &Path) -> (bool, Option<String>) {
+    let mut folders = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .peekable();
+    let offensive = folders
+        .next_if(|folder| folder == OFFENSIVE_FOLDER)
+        .is_some();
+    let category = folders.collect::<Vec<_>>().join("/");
+    (
+        offensive,
+        Some(category).filter(|category| !category.is_empty()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_picks_out_the_fortune_files() {
+        let paths = [
+            ".hidden.dat",
+            ".git/startrek.dat",
+            "humor/puns",
+            "humor/puns.encoding",
+            "humor/puns.toml",
+            "off/limerick.gz",
+            "startrek",
+            "startrek.dat",
+            "startrek.idx",
+            "zippy.gz",
+            "zippy.dat",
+        ]
+        .map(PathBuf::from);
+        assert_eq!(
+            list(&paths, false),
+            [
+                Listed::Unindexed(PathBuf::from("humor/puns"), None),
+                Listed::Unindexed(PathBuf::from("off/limerick.gz"), Some(Compression::Gzip)),
+                Listed::Indexed(PathBuf::from("startrek.dat")),
+                Listed::Indexed(PathBuf::from("zippy.dat")),
+            ]
+        );
+        assert_eq!(
+            list(&paths, true),
+            [
+                Listed::Indexed(PathBuf::from("startrek.dat")),
+                Listed::Indexed(PathBuf::from("zippy.dat")),
+            ]
+        );
+    }
+    #[test]
+    fn placement_is_the_offensive_folder_and_category() {
+        assert_eq!(placement(Path::new("startrek.dat")), (false, None));
+        assert_eq!(
+            placement(Path::new("humor/puns/bad.dat")),
+            (false, Some("humor/puns".to_owned()))
+        );
+        assert_eq!(placement(Path::new("off/limerick.dat")), (true, None));
+        assert_eq!(
+            placement(Path::new("off/humor/limerick.dat")),
+            (true, Some("humor".to_owned()))
+        );
+        assert_eq!(
+            placement(Path::new("humor/off/limerick.dat")),
+            (false, Some("humor/off".to_owned()))
+        );
+    }
+
+    #[tokio::test]
+    async fn walk_lists_every_file_but_hidden_ones() {
+        let data_path = std::env::temp_dir()
+            .join(format!("fortune-listing-{}", std::process::id()))
+            .join("walk");
+        let _ = std::fs::remove_dir_all(&data_path);
+        for folder in ["humor/puns", "off", ".git"] {
+            std::fs::create_dir_all(data_path.join(folder)).unwrap();
+        }
+        for file in [
+            "startrek",
+            "startrek.dat",
+            ".hidden",
+            "humor/puns/bad",
+            "off/limerick.gz",
+            ".git/config",
+        ] {
+            std::fs::write(data_path.join(file), "").unwrap();
+        }
+        assert_eq!(
+            walk(&data_path).await.unwrap(),
+            [
+                "humor/puns/bad",
+                "off/limerick.gz",
+                "startrek",
+                "startrek.dat"
+            ]
+            .map(PathBuf::from)
+        );
+        assert!(matches!(
+            walk(&data_path.join("missing")).await,
+            Err(FortuneError::DataFolder { .. })
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::{FortuneError, Result};
+
+/// The extension of a fortune file's manifest, e.g. `startrek.toml`.
+pub const MANIFEST_EXTENSION: &str = "toml";
+
+/// A fortune file's metadata, read from the optional manifest next to its text file. Every
+/// field is optional, and fields the library doesn't know are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Manifest {
+    title: Option<String>,
+    description: Option<String>,
+    language: Option<String>,
+    license: Option<String>,
+    source: Option<String>,
+    weight: Option<f64>,
+    // Reported as part of the file's information instead
+    #[serde(skip_serializing)]
+    offensive: bool,
+}
+impl Manifest {
+    /// A human-readable name for the file, e.g. `Star Trek quotes`
+    pub fn title(&self) -> Option<String> {
+        self.title.to_owned()
+    }
+    pub fn description(&self) -> Option<String> {
+        self.description.to_owned()
+    }
+    /// The language of the fortunes, e.g. `en`
+    pub fn language(&self) -> Option<String> {
+        self.language.to_owned()
+    }
+    pub fn license(&self) -> Option<String> {
+        self.license.to_owned()
+    }
+    /// Where the fortunes originally came from, e.g. a URL
+    pub fn source(&self) -> Option<String> {
+        self.source.to_owned()
+    }
+    /// How much more or less likely the file is to be chosen than its size alone makes it,
+    /// e.g. `2.0` for twice as likely, or `0.0` for never, unless it is asked for
+    pub fn weight(&self) -> Option<f64> {
+        self.weight
+    }
+    /// If true, the file is offensive, as if it were in the offensive folder
+    pub fn is_offensive(&self) -> bool {
+        self.offensive
+    }
+}
+
+/// Returns the path of the manifest of the given text file.
+pub fn manifest_path(text_path: &Path) -> PathBuf {
+    text_path.with_extension(MANIFEST_EXTENSION)
+}
+
+/// Reads the manifest of the given text file, if it has one.
+pub(crate) async fn read_manifest(text_path: &Path) -> Result<Option<Manifest>> {
+    let path = manifest_path(text_path);
+    let manifest = match fs::read_to_string(&path).await {
+        Ok(manifest) => manifest,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(FortuneError::io(&path, error)),
+    };
+    parse_manifest(&path, &manifest).map(Some)
+}
+
+/// Parses the manifest with the given path from its text.
+pub(crate) fn parse_manifest(path: &Path, manifest: &str) -> Result<Manifest> {
+    toml::from_str(manifest).map_err(|error| FortuneError::InvalidManifest {
+        path: path.to_owned(),
+        message: error.message().to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest_reads_known_fields() {
+        let manifest = parse_manifest(
+            Path::new("startrek.toml"),
+            "title = \"Star Trek\"\nweight = 2.0\noffensive = true\nstardate = 1312.4\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.title().as_deref(), Some("Star Trek"));
+        assert_eq!(manifest.weight(), Some(2.0));
+        assert!(manifest.is_offensive());
+        assert_eq!(manifest.language(), None);
+        assert_eq!(
+            parse_manifest(Path::new("empty.toml"), "").unwrap(),
+            Manifest::default()
+        );
+    }
+    #[test]
+    fn parse_manifest_rejects_malformed_lines() {
+        for manifest in [
+            "title = Star Trek",
+            "title = \"Star Trek\"\nweight",
+            "weight = \"heavy\"",
+        ] {
+            assert!(
+                matches!(
+                    parse_manifest(Path::new("startrek.toml"), manifest),
+                    Err(FortuneError::InvalidManifest { .. })
+                ),
+                "{:?}",
+                manifest
+            );
+        }
+    }
+}
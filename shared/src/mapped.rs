@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::path::Path;
+
+use memmap2::Mmap;
+
+/// Maps a file into memory, read only.
+pub(crate) fn map(path: &Path) -> std::io::Result<Mmap> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: The mapping is only ever read, but if the file is truncated while it is mapped,
+    // reading past its new end raises SIGBUS, which kills the process. Mapping is opt-in for
+    // that reason, and fortune files that are in use must be replaced, not changed in place.
+    unsafe { Mmap::map(&file) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Corpus, DataFile, FortuneOptions};
+
+    #[tokio::test]
+    async fn fortunes_are_read_from_mapped_files() {
+        let data_path = std::env::temp_dir()
+            .join(format!("fortune-mapped-{}", std::process::id()))
+            .join("mapped");
+        let _ = std::fs::remove_dir_all(&data_path);
+        std::fs::create_dir_all(&data_path).unwrap();
+        let text = b"Kirk\n%\nSpock\n%\n";
+        let data_file: Vec<u8> = DataFile::parse(text, None, None).to_bytes().collect();
+        std::fs::write(data_path.join("startrek"), text).unwrap();
+        std::fs::write(data_path.join("startrek.dat"), data_file).unwrap();
+        std::fs::write(data_path.join("zippy"), "Yow!\n%\n").unwrap();
+
+        let mut options = FortuneOptions::default();
+        options.set_mapped(true);
+        let corpus = Corpus::load_with(&data_path, &options).await.unwrap();
+        // Without mapping, the text of a file on disk isn't kept in memory
+        for fortune_file_info in corpus.fortune_files(options.collection()) {
+            assert!(fortune_file_info.loaded_text().is_some());
+        }
+        let fortune = corpus.fortune_by_id("startrek/1").await.unwrap();
+        assert_eq!(fortune.fortune(), "Spock\n");
+        let fortune = corpus.fortune_by_id("zippy/0").await.unwrap();
+        assert_eq!(fortune.fortune(), "Yow!\n");
+
+        options.set_mapped(false);
+        let corpus = Corpus::load_with(&data_path, &options).await.unwrap();
+        for fortune_file_info in corpus.fortune_files(options.collection()) {
+            assert!(fortune_file_info.loaded_text().is_none());
+        }
+    }
+}
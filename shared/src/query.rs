@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use super::{words, FortuneError, Result};
+
+/// A search for fortunes by the words in them, which ignores case and punctuation. It can be
+/// answered from a fortune file's [word index](super::WordIndex), if it has one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// Fortunes that have the word
+    Word(String),
+    /// Fortunes that have the words one after another
+    Phrase(Vec<String>),
+    /// Fortunes that match all the queries
+    And(Vec<Query>),
+    /// Fortunes that match any of the queries
+    Or(Vec<Query>),
+}
+impl Query {
+    /// Parses a query such as `rust "memory safety" OR borrow checker`. Words and quoted
+    /// phrases must all match, and `OR` separates alternatives, so that example matches
+    /// fortunes with both `rust` and the phrase `memory safety`, or both `borrow` and `checker`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = |message: &str| FortuneError::InvalidQuery {
+            query: text.to_owned(),
+            message: message.to_owned(),
+        };
+        let mut alternatives = vec![];
+        let mut terms = vec![];
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let term;
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted
+                    .find('"')
+                    .ok_or_else(|| invalid("A phrase has no closing quote"))?;
+                term = Self::phrase(&quoted[..end]);
+                rest = &quoted[end + 1..];
+            } else {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '"')
+                    .unwrap_or(rest.len());
+                if &rest[..end] == "OR" {
+                    if terms.is_empty() {
+                        return Err(invalid("OR must come between words or phrases"));
+                    }
+                    alternatives.push(Self::all(std::mem::take(&mut terms)));
+                    term = None;
+                } else {
+                    term = Self::phrase(&rest[..end]);
+                }
+                rest = &rest[end..];
+            }
+            terms.extend(term);
+            rest = rest.trim_start();
+        }
+        if terms.is_empty() {
+            return Err(invalid(if alternatives.is_empty() {
+                "There are no words to search for"
+            } else {
+                "OR must come between words or phrases"
+            }));
+        }
+        alternatives.push(Self::all(terms));
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Query::Or(alternatives)
+        })
+    }
+    /// Returns true if the text matches the query.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.matches(&words(text).collect::<Vec<_>>())
+    }
+    fn matches(&self, words: &[String]) -> bool {
+        match self {
+            Query::Word(word) => words.contains(word),
+            Query::Phrase(phrase) => words
+                .windows(phrase.len())
+                .any(|window| window == phrase.as_slice()),
+            Query::And(queries) => queries.iter().all(|query| query.matches(words)),
+            Query::Or(queries) => queries.iter().any(|query| query.matches(words)),
+        }
+    }
+    /// Returns the query for the words in the text, which must appear together if there is
+    /// more than one, or `None` if there are none.
+    fn phrase(text: &str) -> Option<Self> {
+        let mut phrase: Vec<String> = words(text).collect();
+        match phrase.len() {
+            0 => None,
+            1 => phrase.pop().map(Query::Word),
+            _ => Some(Query::Phrase(phrase)),
+        }
+    }
+    fn all(mut terms: Vec<Query>) -> Self {
+        if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Query::And(terms)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str) -> Query {
+        Query::Word(word.to_owned())
+    }
+
+    #[test]
+    fn parse_words_phrases_and_alternatives() {
+        let query = Query::parse(r#"Rust "memory safety" OR borrow checker"#).unwrap();
+        assert_eq!(
+            query,
+            Query::Or(vec![
+                Query::And(vec![
+                    word("rust"),
+                    Query::Phrase(vec!["memory".to_owned(), "safety".to_owned()]),
+                ]),
+                Query::And(vec![word("borrow"), word("checker")]),
+            ])
+        );
+        assert_eq!(Query::parse("  Spock!  ").unwrap(), word("spock"));
+    }
+    #[test]
+    fn parse_rejects_empty_queries() {
+        for text in [
+            "",
+            "  ",
+            "OR spock",
+            "spock OR",
+            "\"unclosed phrase",
+            "\"!!\"",
+        ] {
+            assert!(
+                matches!(Query::parse(text), Err(FortuneError::InvalidQuery { .. })),
+                "{:?}",
+                text
+            );
+        }
+    }
+    #[test]
+    fn is_match_ignores_case_and_punctuation() {
+        let query = Query::parse(r#""beam me up" OR phasers"#).unwrap();
+        assert!(query.is_match("Beam me up, Scotty!"));
+        assert!(query.is_match("Set PHASERS to stun."));
+        assert!(!query.is_match("Beam up me, Scotty."));
+    }
+}
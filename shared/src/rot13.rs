@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+
+/// Encodes or decodes ROT13 text in place. Only ASCII letters are changed,
+/// so the text stays the same length, and UTF-8 text stays valid.
+pub fn rot13(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        *byte = match byte {
+            b'a'..=b'm' | b'A'..=b'M' => *byte + 13,
+            b'n'..=b'z' | b'N'..=b'Z' => *byte - 13,
+            _ => *byte,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rot13_round_trips() {
+        let mut bytes = b"Why did the chicken cross the road?".to_vec();
+        rot13(&mut bytes);
+        assert_eq!(bytes, b"Jul qvq gur puvpxra pebff gur ebnq?");
+        rot13(&mut bytes);
+        assert_eq!(bytes, b"Why did the chicken cross the road?");
+    }
+    #[test]
+    fn rot13_leaves_non_ascii_alone() {
+        let text = "Café — naïve ★ 42%\n";
+        let mut bytes = text.as_bytes().to_vec();
+        rot13(&mut bytes);
+        assert_eq!(String::from_utf8(bytes).unwrap(), "Pnsé — anïir ★ 42%\n");
+    }
+}
@@ -0,0 +1,291 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::borrow::Cow;
+use std::vec::IntoIter;
+
+use regex::{Regex, RegexBuilder};
+
+use super::{
+    fortune_in_text, read_fortune, Collection, Decoding, Fortune, FortuneError, FortuneFileInfo,
+    FortuneSource, Query, Result,
+};
+
+/// What to search the fortunes for, and where.
+#[derive(Clone, Debug, Default)]
+pub struct SearchOptions {
+    pattern: String,
+    case_insensitive: bool,
+    files: Vec<String>,
+    collection: Collection,
+    query: Option<Query>,
+    author: Option<String>,
+}
+impl SearchOptions {
+    /// Creates options to search for the given regular expression.
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            ..Default::default()
+        }
+    }
+    pub fn pattern(&self) -> String {
+        self.pattern.to_owned()
+    }
+    pub fn case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) -> &mut Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+    /// The names of the fortune files to search. If it's empty, all the files are searched.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+    pub fn set_files(&mut self, files: Vec<String>) -> &mut Self {
+        self.files = files;
+        self
+    }
+    pub fn collection(&self) -> Collection {
+        self.collection
+    }
+    pub fn set_collection(&mut self, collection: Collection) -> &mut Self {
+        self.collection = collection;
+        self
+    }
+    /// Only fortunes that match this word query match. Fortune files with word indexes are
+    /// searched without reading all their fortunes
+    pub fn query(&self) -> Option<&Query> {
+        self.query.as_ref()
+    }
+    pub fn set_query(&mut self, query: Option<Query>) -> &mut Self {
+        self.query = query;
+        self
+    }
+    /// Only fortunes whose author contains this name, ignoring case, match
+    pub fn author(&self) -> Option<String> {
+        self.author.to_owned()
+    }
+    pub fn set_author(&mut self, author: Option<String>) -> &mut Self {
+        self.author = author;
+        self
+    }
+    pub(crate) fn regex(&self) -> Result<Regex> {
+        RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.case_insensitive)
+            .build()
+            .map_err(|error| FortuneError::InvalidPattern {
+                pattern: self.pattern(),
+                message: error.to_string(),
+            })
+    }
+}
+
+/// The fortunes that match a search, found one at a time. Each fortune file's text
+/// is read only when the search reaches it. If the search has a [Query], and a file has a
+/// [word index](crate::WordIndex), only the fortunes that the index finds are read instead.
+pub struct Search<'a> {
+    regex: Regex,
+    query: Option<Query>,
+    author: Option<String>,
+    source: &'a dyn FortuneSource,
+    fortune_files: IntoIter<&'a FortuneFileInfo>,
+    current: Option<Current<'a>>,
+    decoding: Decoding,
+}
+/// The fortune file that a search is in, and the fortunes in it that are left to check.
+struct Current<'a> {
+    fortune_file_info: &'a FortuneFileInfo,
+    /// The file's text, unless its word index found the fortunes to check
+    text: Option<Cow<'a, [u8]>>,
+    indexes: IntoIter<usize>,
+}
+impl<'a> Search<'a> {
+    pub(crate) fn new(
+        options: &SearchOptions,
+        source: &'a dyn FortuneSource,
+        fortune_files: Vec<&'a FortuneFileInfo>,
+        decoding: Decoding,
+    ) -> Result<Self> {
+        Ok(Self {
+            regex: options.regex()?,
+            query: options.query().cloned(),
+            author: options.author(),
+            source,
+            fortune_files: fortune_files.into_iter(),
+            current: None,
+            decoding,
+        })
+    }
+    /// Returns the next fortune that matches, or `None` when there are no more.
+    pub async fn next_match(&mut self) -> Option<Result<Fortune>> {
+        loop {
+            if self.current.is_none() {
+                let fortune_file_info = self.fortune_files.next()?;
+                match self.start(fortune_file_info).await {
+                    Ok(current) => self.current = Some(current),
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+            let current = self.current.as_mut()?;
+            let Some(index) = current.indexes.next() else {
+                self.current = None;
+                continue;
+            };
+            let fortune = match &current.text {
+                Some(text) => {
+                    fortune_in_text(current.fortune_file_info, index, text, self.decoding)
+                }
+                None => {
+                    read_fortune(self.source, current.fortune_file_info, index, self.decoding).await
+                }
+            };
+            match fortune {
+                Ok(fortune) if self.is_match(&fortune) => return Some(Ok(fortune)),
+                Ok(_) => continue,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+    /// Finds the fortunes to check in a fortune file, using its word index if it has one and
+    /// the search has a query, and otherwise reading its text.
+    async fn start(&self, fortune_file_info: &'a FortuneFileInfo) -> Result<Current<'a>> {
+        if let Some(query) = &self.query {
+            if let Some(word_index) = fortune_file_info.word_index(self.source).await {
+                return Ok(Current {
+                    fortune_file_info,
+                    text: None,
+                    indexes: word_index.candidates(query).into_iter(),
+                });
+            }
+        }
+        let text = self.source.text(fortune_file_info).await?;
+        Ok(Current {
+            fortune_file_info,
+            text: Some(text),
+            indexes: (0..fortune_file_info.count())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        })
+    }
+    /// Returns true if the fortune matches the pattern, and the query and author if they
+    /// were given.
+    fn is_match(&self, fortune: &Fortune) -> bool {
+        self.regex.is_match(&fortune.fortune())
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.is_match(&fortune.fortune()))
+            && self
+                .author
+                .as_ref()
+                .is_none_or(|author| fortune.is_by(author))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::super::corpus::tests::{memory_corpus, FILES};
+    use super::super::{word_index, Corpus, DataFile, FortuneOptions, MemorySource, WordIndex};
+    use super::*;
+
+    /// Returns the identifiers of the fortunes that the search finds.
+    async fn found(corpus: &Corpus, options: &SearchOptions) -> Vec<String> {
+        let mut search = corpus.search(options).unwrap();
+        let mut ids = vec![];
+        while let Some(fortune) = search.next_match().await {
+            ids.push(fortune.unwrap().id());
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn search_finds_fortunes_that_match_the_pattern() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = SearchOptions::new("^(Kirk|Yow)");
+        assert_eq!(found(&corpus, &options).await, ["startrek/0", "zippy/0"]);
+        options = SearchOptions::new("pun");
+        assert_eq!(
+            found(&corpus, &options).await,
+            ["humor/puns/0", "humor/puns/1"]
+        );
+        options = SearchOptions::new("(");
+        assert!(matches!(
+            corpus.search(&options),
+            Err(FortuneError::InvalidPattern { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn search_can_ignore_case() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = SearchOptions::new("spock|yow");
+        assert!(found(&corpus, &options).await.is_empty());
+        options.set_case_insensitive(true);
+        assert_eq!(found(&corpus, &options).await, ["startrek/1", "zippy/0"]);
+    }
+    #[tokio::test]
+    async fn search_is_limited_to_the_files_given() {
+        let corpus = memory_corpus(FILES).await;
+        let mut options = SearchOptions::new("");
+        options.set_files(vec!["zippy".to_owned(), "humor".to_owned()]);
+        assert_eq!(
+            found(&corpus, &options).await,
+            ["humor/puns/0", "humor/puns/1", "zippy/0"]
+        );
+        options.set_files(vec!["vulcan".to_owned()]);
+        assert!(matches!(
+            corpus.search(&options),
+            Err(FortuneError::UnknownSource { name, .. }) if name == "vulcan"
+        ));
+    }
+    #[tokio::test]
+    async fn search_can_find_fortunes_by_an_author() {
+        let corpus = memory_corpus(&[(
+            "quotes",
+            "Always do right.\n\t\t-- Mark Twain\n%\nYow!\n\t\t-- Zippy\n%\nNo one said this.\n%\n",
+        )])
+        .await;
+        let mut options = SearchOptions::new("");
+        options.set_author(Some("twain".to_owned()));
+        assert_eq!(found(&corpus, &options).await, ["quotes/0"]);
+        options
+            .set_author(Some("Mark".to_owned()))
+            .set_files(vec!["quotes".to_owned()]);
+        assert_eq!(found(&corpus, &options).await, ["quotes/0"]);
+    }
+    #[tokio::test]
+    async fn query_uses_the_word_index_when_there_is_one() {
+        let text = b"Kirk\n%\nSpock and Kirk\n%\nMcCoy\n%\n";
+        let data_file = DataFile::parse(text, None, None);
+        // An index made from other text shows which fortunes the index was used to find
+        let other_text = b"Scotty\n%\nKirk\n%\nMcCoy\n%\n";
+        let other_index =
+            WordIndex::build(other_text, &DataFile::parse(other_text, None, None), None);
+        let mut source = MemorySource::new();
+        source
+            .insert(Path::new("indexed"), text.to_vec())
+            .insert(
+                Path::new("indexed.dat"),
+                data_file.to_bytes().collect::<Vec<_>>(),
+            )
+            .insert(
+                &word_index::word_index_path(Path::new("indexed.dat")),
+                other_index.to_bytes(),
+            )
+            .insert(Path::new("scanned"), text.to_vec());
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        let mut options = SearchOptions::new("");
+        options.set_query(Some(Query::parse("kirk").unwrap()));
+        assert_eq!(
+            found(&corpus, &options).await,
+            ["indexed/1", "scanned/0", "scanned/1"]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use super::{FortuneError, Result};
+
+/// A fortune file to choose from, optionally with the percentage of the fortunes that should
+/// come from it, e.g. `30% startrek`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    name: String,
+    percent: Option<f64>,
+}
+impl Source {
+    pub fn new(name: String, percent: Option<f64>) -> Self {
+        Self { name, percent }
+    }
+    pub fn name(&self) -> String {
+        self.name.to_owned()
+    }
+    pub fn percent(&self) -> Option<f64> {
+        self.percent
+    }
+    /// Parses a list of sources the way BSD `fortune` does, e.g. `["30%", "startrek", "70%", "zippy"]`.
+    /// A percentage may be a separate argument or joined to the name, as in `30%startrek`,
+    /// and it applies to the name that follows it.
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Result<Vec<Source>> {
+        let mut sources = vec![];
+        let mut percent: Option<(f64, String)> = None;
+        for arg in args {
+            let arg = arg.as_ref();
+            let invalid = || FortuneError::InvalidSource {
+                spec: arg.to_owned(),
+            };
+            let name = match arg.split_once('%') {
+                Some((number, name)) => {
+                    if percent.is_some() {
+                        return Err(invalid());
+                    }
+                    let number = number.trim().parse::<f64>().map_err(|_| invalid())?;
+                    if !(0.0..=100.0).contains(&number) {
+                        return Err(invalid());
+                    }
+                    percent = Some((number, arg.to_owned()));
+                    name
+                }
+                None => arg,
+            };
+            if name.is_empty() {
+                continue;
+            }
+            sources.push(Source::new(
+                name.to_owned(),
+                percent.take().map(|(number, _)| number),
+            ));
+        }
+        // A percentage must be followed by a name
+        match percent {
+            Some((_, spec)) => Err(FortuneError::InvalidSource { spec }),
+            None => Ok(sources),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_separate_and_joined_percentages() {
+        let sources = Source::parse(&["30%", "startrek", "70%zippy", "humor"]).unwrap();
+        assert_eq!(
+            sources,
+            [
+                Source::new("startrek".to_owned(), Some(30.0)),
+                Source::new("zippy".to_owned(), Some(70.0)),
+                Source::new("humor".to_owned(), None),
+            ]
+        );
+    }
+    #[test]
+    fn parse_rejects_bad_percentages() {
+        for args in [
+            &["30%"][..],
+            &["30%", "40%", "startrek"],
+            &["x%", "startrek"],
+            &["101%", "startrek"],
+        ] {
+            assert!(
+                matches!(Source::parse(args), Err(FortuneError::InvalidSource { .. })),
+                "{:?}",
+                args
+            );
+        }
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use encoding_rs::{Encoding, WINDOWS_1252};
+use tokio::fs;
+
+use super::{FortuneError, Result};
+
+/// The extension of the file that declares the encoding of a fortune text file,
+/// e.g. `startrek.encoding`, which holds a label such as `latin1`.
+pub const ENCODING_EXTENSION: &str = "encoding";
+
+/// What to do with a fortune that isn't valid in its encoding.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Decoding {
+    /// If the file's encoding isn't declared, detect it; replace anything that isn't valid
+    #[default]
+    Lossy,
+    /// The fortune must be valid in the declared encoding, or UTF-8 if none is declared
+    Strict,
+}
+
+/// Returns the encoding with the given label, e.g. `utf-8`, `latin1` or `windows-1252`.
+/// Only encodings in which the separator lines are plain ASCII are supported.
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes()).filter(|encoding| encoding.is_ascii_compatible())
+}
+
+/// Returns the path of the file that declares the encoding of the given text file.
+pub fn encoding_path(text_path: &Path) -> PathBuf {
+    text_path.with_extension(ENCODING_EXTENSION)
+}
+
+/// Reads the encoding declared for the given text file, if there is one.
+pub async fn declared_encoding(text_path: &Path) -> Result<Option<&'static Encoding>> {
+    let path = encoding_path(text_path);
+    let label = match fs::read_to_string(&path).await {
+        Ok(label) => label,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(FortuneError::io(&path, error)),
+    };
+    parse_declaration(&path, &label).map(Some)
+}
+
+/// Returns the encoding with the label in the declaration file with the given path.
+pub(crate) fn parse_declaration(path: &Path, label: &str) -> Result<&'static Encoding> {
+    encoding_for_label(label).ok_or_else(|| FortuneError::UnknownEncoding {
+        path: path.to_owned(),
+        label: label.trim().to_owned(),
+    })
+}
+
+/// Decodes a fortune's text. If no encoding is given, the text is UTF-8 if it is valid
+/// UTF-8, and otherwise it is taken to be Windows-1252, which includes Latin-1. Returns
+/// `None` if the decoding is strict and the text isn't valid.
+pub fn decode(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+    decoding: Decoding,
+) -> Option<String> {
+    match (encoding, decoding) {
+        (Some(encoding), Decoding::Strict) => encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|text| text.into_owned()),
+        (Some(encoding), Decoding::Lossy) => {
+            Some(encoding.decode_without_bom_handling(bytes).0.into_owned())
+        }
+        (None, Decoding::Strict) => std::str::from_utf8(bytes).ok().map(str::to_owned),
+        (None, Decoding::Lossy) => match std::str::from_utf8(bytes) {
+            Ok(text) => Some(text.to_owned()),
+            Err(_) => Some(
+                WINDOWS_1252
+                    .decode_without_bom_handling(bytes)
+                    .0
+                    .into_owned(),
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::UTF_8;
+
+    use super::*;
+
+    const LATIN_1: &[u8] = b"Caf\xe9 cr\xe8me\n";
+
+    #[test]
+    fn decode_declared_latin_1() {
+        let latin1 = encoding_for_label("latin1");
+        assert_eq!(latin1, Some(WINDOWS_1252));
+        for decoding in [Decoding::Lossy, Decoding::Strict] {
+            assert_eq!(
+                decode(LATIN_1, latin1, decoding).as_deref(),
+                Some("Café crème\n")
+            );
+        }
+        // Undeclared text that isn't UTF-8 is read as Windows-1252, unless decoding is strict
+        assert_eq!(
+            decode(LATIN_1, None, Decoding::Lossy).as_deref(),
+            Some("Café crème\n")
+        );
+        assert_eq!(decode(LATIN_1, None, Decoding::Strict), None);
+    }
+    #[test]
+    fn decode_invalid_bytes() {
+        // Strict decoding fails, and lossy decoding replaces what isn't valid
+        assert_eq!(decode(LATIN_1, Some(UTF_8), Decoding::Strict), None);
+        assert_eq!(
+            decode(LATIN_1, Some(UTF_8), Decoding::Lossy).as_deref(),
+            Some("Caf� cr�me\n")
+        );
+        assert_eq!(
+            decode("Café\n".as_bytes(), None, Decoding::Strict).as_deref(),
+            Some("Café\n")
+        );
+    }
+    #[test]
+    fn encoding_for_label_needs_ascii_separators() {
+        assert_eq!(encoding_for_label(" utf-8\n"), Some(UTF_8));
+        assert_eq!(encoding_for_label("utf-16le"), None);
+        assert_eq!(encoding_for_label("klingon"), None);
+    }
+}
@@ -0,0 +1,406 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::fs;
+
+use super::compression::{compressed_path, find_compression};
+use super::{
+    compression, header_separator, listing, DataFile, Flags, FortuneError, Header, Result,
+    Separator,
+};
+
+/// The size of a `.dat` file's header, which is six 32-bit numbers.
+const HEADER_SIZE: usize = 24;
+
+/// Something wrong with a `.dat` file or the text file it indexes.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    /// The `.dat` file has a version this library doesn't understand
+    BadVersion { version: u32 },
+    /// The `.dat` file is too short to hold a header
+    TruncatedHeader,
+    /// The separator stored after the offsets can't be read
+    BadSeparator,
+    /// The header's count doesn't match the number of offsets, which should be one more
+    CountMismatch { count: u32, offsets: usize },
+    /// An offset isn't greater than the one before it
+    OffsetOutOfOrder { index: usize, offset: u32 },
+    /// An offset is past the end of the text file
+    OffsetOutOfRange {
+        index: usize,
+        offset: u32,
+        size: u64,
+    },
+    /// A fortune doesn't end with a separator line
+    MissingSeparator { index: usize },
+    /// The text file has changed since the `.dat` file was made
+    TextNewerThanIndex,
+    /// The text file doesn't exist
+    MissingTextFile,
+    /// A file can't be read
+    Unreadable { error: String },
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::BadVersion { version } => write!(f, "Unsupported version {}", version),
+            Problem::TruncatedHeader => write!(f, "The header is truncated"),
+            Problem::BadSeparator => write!(f, "The stored separator can't be read"),
+            Problem::CountMismatch { count, offsets } => write!(
+                f,
+                "The count is {}, but there are {} offsets instead of {}",
+                count,
+                offsets,
+                *count as u64 + 1
+            ),
+            Problem::OffsetOutOfOrder { index, offset } => write!(
+                f,
+                "Offset {} ({}) isn't greater than the one before it",
+                index, offset
+            ),
+            Problem::OffsetOutOfRange {
+                index,
+                offset,
+                size,
+            } => write!(
+                f,
+                "Offset {} ({}) is past the end of the text file ({} bytes)",
+                index, offset, size
+            ),
+            Problem::MissingSeparator { index } => {
+                write!(f, "Fortune {} doesn't end with a separator line", index)
+            }
+            Problem::TextNewerThanIndex => write!(f, "The text file is newer than the index"),
+            Problem::MissingTextFile => write!(f, "The text file is missing"),
+            Problem::Unreadable { error } => write!(f, "Cannot read the file: {}", error),
+        }
+    }
+}
+
+/// A [Problem] found in the `.dat` file with the given path.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Issue {
+    path: PathBuf,
+    #[serde(flatten)]
+    problem: Problem,
+    message: String,
+}
+impl Issue {
+    pub fn new(path: &Path, problem: Problem) -> Self {
+        Self {
+            path: path.to_owned(),
+            message: problem.to_string(),
+            problem,
+        }
+    }
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    pub fn problem(&self) -> &Problem {
+        &self.problem
+    }
+}
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}': {}", self.path.display(), self.problem)
+    }
+}
+
+/// Checks every `.dat` file in the given data folders and their subfolders against the text
+/// file it indexes, and returns the problems found. Folders that don't exist are skipped, but
+/// at least one must.
+pub async fn validate(data_paths: &[PathBuf]) -> Result<Vec<Issue>> {
+    let existing_paths: Vec<&PathBuf> = data_paths.iter().filter(|path| path.is_dir()).collect();
+    if existing_paths.is_empty() {
+        return Err(FortuneError::DataFolder {
+            path: data_paths.first().cloned().unwrap_or_default(),
+            source: std::io::Error::from(ErrorKind::NotFound),
+        });
+    }
+    let mut issues = vec![];
+    for data_path in existing_paths {
+        for dat_path in dat_files(data_path).await? {
+            let mut text_path = dat_path.with_extension("");
+            if let Some(compression) = find_compression(&text_path) {
+                text_path = compressed_path(&text_path, compression);
+            }
+            issues.append(&mut validate_data_file(&dat_path, &text_path).await);
+        }
+    }
+    Ok(issues)
+}
+
+/// Checks a `.dat` file against the text file it indexes, which is decompressed if its
+/// extension names a compression, and returns the problems found:
+/// a bad header, a count that doesn't match the offsets, offsets that are out of order or
+/// past the end of the text, fortunes that don't end with a separator line, and text that
+/// is newer than the index.
+pub async fn validate_data_file(dat_path: &Path, text_path: &Path) -> Vec<Issue> {
+    let issue = |problem| vec![Issue::new(dat_path, problem)];
+    let unreadable = |error: std::io::Error| Problem::Unreadable {
+        error: error.to_string(),
+    };
+    let bytes = match fs::read(dat_path).await {
+        Ok(bytes) => bytes,
+        Err(error) => return issue(unreadable(error)),
+    };
+    if bytes.len() < HEADER_SIZE {
+        return issue(Problem::TruncatedHeader);
+    }
+    let numbers: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    let version = numbers[0];
+    if version != Header::default().version() {
+        return issue(Problem::BadVersion { version });
+    }
+    let count = numbers[1];
+    let flags = Flags::from_bits(numbers[4]).unwrap_or_default();
+    let mut offsets = &numbers[HEADER_SIZE / 4..];
+
+    let mut issues = vec![];
+    let mut add = |problem| issues.push(Issue::new(dat_path, problem));
+    let mut separator = Separator::from(header_separator(numbers[5]));
+    let mut separator_lengths = vec![];
+    if flags.contains(Flags::Separator) {
+        // The separator follows the offsets, so the count says where they end
+        let stored = offsets
+            .split_at_checked(count as usize + 1)
+            .and_then(|(head, tail)| Some((head, DataFile::read_separator(tail, count)?)));
+        match stored {
+            Some((head, stored)) => {
+                offsets = head;
+                (separator, separator_lengths) = stored;
+            }
+            None => {
+                add(Problem::BadSeparator);
+                return issues;
+            }
+        }
+    }
+    if bytes.len() % 4 != 0 || offsets.len() as u64 != count as u64 + 1 {
+        add(Problem::CountMismatch {
+            count,
+            offsets: offsets.len(),
+        });
+    }
+
+    let text_metadata = match fs::metadata(text_path).await {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            add(Problem::MissingTextFile);
+            return issues;
+        }
+        Err(error) => {
+            add(unreadable(error));
+            return issues;
+        }
+    };
+    let dat_modified = fs::metadata(dat_path)
+        .await
+        .and_then(|metadata| metadata.modified());
+    if let (Ok(text_modified), Ok(dat_modified)) = (text_metadata.modified(), dat_modified) {
+        if text_modified > dat_modified {
+            add(Problem::TextNewerThanIndex);
+        }
+    }
+    let text = match compression::read_text(text_path).await {
+        Ok(text) => text,
+        Err(error) => {
+            add(unreadable(error));
+            return issues;
+        }
+    };
+    let size = text.len() as u64;
+    for (index, offset) in offsets.iter().enumerate() {
+        if index > 0 && *offset <= offsets[index - 1] {
+            add(Problem::OffsetOutOfOrder {
+                index,
+                offset: *offset,
+            });
+        } else if *offset as u64 > size {
+            add(Problem::OffsetOutOfRange {
+                index,
+                offset: *offset,
+                size,
+            });
+        } else if index > 0 && index <= count as usize {
+            // The fortune before this offset must end with its separator line
+            let end = *offset as usize;
+            let length = separator.line_length().or_else(|| {
+                separator_lengths
+                    .get(index - 1)
+                    .map(|length| *length as usize)
+            });
+            let line = length
+                .and_then(|length| end.checked_sub(length))
+                .and_then(|start| text.get(start..end));
+            if !line.is_some_and(|line| separator.is_match(line)) {
+                add(Problem::MissingSeparator { index: index - 1 });
+            }
+        }
+    }
+    issues
+}
+
+/// Returns the paths of the `.dat` files in the given folder and its subfolders, including
+/// the offensive folder, but not hidden files and folders.
+async fn dat_files(data_path: &Path) -> Result<Vec<PathBuf>> {
+    Ok(listing::walk(data_path)
+        .await?
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dat"))
+        .map(|path| data_path.join(path))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Corpus, FortuneOptions, MemorySource};
+
+    const TEXT: &[u8] = b"Kirk\n%\nSpock\n%\n";
+    /// The text that the mismatched `.dat` file was made from
+    const OLD_TEXT: &[u8] = b"Kirk\n%\nSpock, the Vulcan\n%\n";
+
+    fn mismatched_data_file() -> Vec<u8> {
+        DataFile::parse(OLD_TEXT, None, None).to_bytes().collect()
+    }
+    /// Makes an empty folder for the test with the given name.
+    fn temp_folder(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("fortune-validate-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn validate_finds_problems_in_every_folder() {
+        let data_path = temp_folder("problems");
+        std::fs::create_dir(data_path.join("humor")).unwrap();
+        std::fs::write(data_path.join("startrek"), TEXT).unwrap();
+        std::fs::write(data_path.join("startrek.dat"), mismatched_data_file()).unwrap();
+        std::fs::write(data_path.join("short.dat"), [0, 0, 0, 2]).unwrap();
+        std::fs::write(data_path.join("orphan.dat"), mismatched_data_file()).unwrap();
+        let mut bad_version = mismatched_data_file();
+        bad_version[3] = 9;
+        std::fs::write(data_path.join("humor/puns.dat"), bad_version).unwrap();
+
+        let issues = validate(&[data_path.join("missing"), data_path.clone()])
+            .await
+            .unwrap();
+        let problems: Vec<(PathBuf, Problem)> = issues
+            .iter()
+            .map(|issue| (issue.path().to_owned(), issue.problem().to_owned()))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (
+                    data_path.join("humor/puns.dat"),
+                    Problem::BadVersion { version: 9 }
+                ),
+                (data_path.join("orphan.dat"), Problem::MissingTextFile),
+                (data_path.join("short.dat"), Problem::TruncatedHeader),
+                (
+                    data_path.join("startrek.dat"),
+                    Problem::OffsetOutOfRange {
+                        index: 2,
+                        offset: 27,
+                        size: 15
+                    }
+                ),
+            ]
+        );
+        std::fs::remove_dir_all(&data_path).unwrap();
+    }
+    #[tokio::test]
+    async fn validate_needs_a_data_folder() {
+        let data_path = temp_folder("missing").join("missing");
+        assert!(matches!(
+            validate(&[data_path]).await,
+            Err(FortuneError::DataFolder { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn validate_data_file_finds_missing_separators() {
+        let data_path = temp_folder("separators");
+        let text_path = data_path.join("startrek");
+        let dat_path = data_path.join("startrek.dat");
+        std::fs::write(&text_path, b"Kirk!\n%\nSpock\n%\n").unwrap();
+        let data_file = DataFile::parse(TEXT, None, None);
+        std::fs::write(&dat_path, data_file.to_bytes().collect::<Vec<_>>()).unwrap();
+        let problems: Vec<Problem> = validate_data_file(&dat_path, &text_path)
+            .await
+            .iter()
+            .map(|issue| issue.problem().to_owned())
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                Problem::MissingSeparator { index: 0 },
+                Problem::MissingSeparator { index: 1 }
+            ]
+        );
+        std::fs::remove_dir_all(&data_path).unwrap();
+    }
+    #[tokio::test]
+    async fn mismatched_data_files_fail_to_read() {
+        let mut source = MemorySource::new();
+        source
+            .insert(Path::new("startrek"), TEXT)
+            .insert(Path::new("startrek.dat"), mismatched_data_file());
+        let corpus = Corpus::load_source(Arc::new(source), &FortuneOptions::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            corpus.fortune_by_id("startrek/1").await,
+            Err(FortuneError::OffsetOutOfRange { index: 1, .. })
+        ));
+    }
+    #[test]
+    fn issues_are_json_objects() {
+        let issue = Issue::new(
+            Path::new("startrek.dat"),
+            Problem::OffsetOutOfRange {
+                index: 2,
+                offset: 27,
+                size: 15,
+            },
+        );
+        assert_eq!(
+            serde_json::to_value(&issue).unwrap(),
+            json!({
+                "path": "startrek.dat",
+                "problem": "offset_out_of_range",
+                "index": 2,
+                "offset": 27,
+                "size": 15,
+                "message": "Offset 2 (27) is past the end of the text file (15 bytes)"
+            })
+        );
+        let issue = Issue::new(Path::new("short.dat"), Problem::TruncatedHeader);
+        assert_eq!(
+            serde_json::to_value(&issue).unwrap(),
+            json!({
+                "path": "short.dat",
+                "problem": "truncated_header",
+                "message": "The header is truncated"
+            })
+        );
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use super::{rot13, text_encoding, DataFile, Decoding, Encoding, Query};
+
+/// The extension of a fortune file's word index, e.g. `startrek.idx`.
+pub const WORD_INDEX_EXTENSION: &str = "idx";
+
+/// The version of the word index file format.
+const VERSION: u32 = 1;
+
+/// Splits text into words, which are runs of letters and digits, in lower case.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// The fortunes that each word in a fortune file appears in, which lets a [Query] be answered
+/// without reading the whole file. The file is big-endian, like a `.dat` file: the version,
+/// the number of fortunes and the number of words, followed by each word, as its length and
+/// its UTF-8 bytes, and the indexes of the fortunes it is in, as their number and the indexes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WordIndex {
+    count: u32,
+    words: BTreeMap<String, Vec<u32>>,
+}
+impl WordIndex {
+    /// Indexes the words in a fortune file's text, which is read the way the library reads
+    /// it: decoded from the given encoding, and from ROT13 if the `.dat` file says it is rotated.
+    pub fn build(text: &[u8], data_file: &DataFile, encoding: Option<&'static Encoding>) -> Self {
+        let mut words: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for index in 0..data_file.offsets.len().saturating_sub(1) {
+            let Some(bytes) = data_file
+                .fortune_range(index)
+                .and_then(|range| text.get(range))
+            else {
+                continue;
+            };
+            let mut bytes = bytes.to_vec();
+            if data_file.header.is_rotated() {
+                rot13(&mut bytes);
+            }
+            let fortune =
+                text_encoding::decode(&bytes, encoding, Decoding::Lossy).unwrap_or_default();
+            for word in self::words(&fortune) {
+                let indexes = words.entry(word).or_default();
+                if indexes.last() != Some(&(index as u32)) {
+                    indexes.push(index as u32);
+                }
+            }
+        }
+        Self {
+            count: data_file.header.count(),
+            words,
+        }
+    }
+    /// Returns the number of fortunes in the file that was indexed.
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+    /// Returns the indexes of the fortunes that the word is in, in order.
+    pub fn indexes(&self, word: &str) -> &[u32] {
+        self.words.get(word).map(Vec::as_slice).unwrap_or_default()
+    }
+    /// Returns the indexes of the fortunes that might match the query, in order. Every fortune
+    /// that matches is included, but a phrase's fortunes only have all its words, so they
+    /// still have to be checked.
+    pub fn candidates(&self, query: &Query) -> Vec<usize> {
+        self.postings(query)
+            .into_iter()
+            .map(|index| index as usize)
+            .collect()
+    }
+    fn postings(&self, query: &Query) -> Vec<u32> {
+        match query {
+            Query::Word(word) => self.indexes(word).to_vec(),
+            Query::Phrase(words) => {
+                intersection(words.iter().map(|word| self.indexes(word).to_vec()))
+            }
+            Query::And(queries) => intersection(queries.iter().map(|query| self.postings(query))),
+            Query::Or(queries) => {
+                let mut indexes: Vec<u32> = queries
+                    .iter()
+                    .flat_map(|query| self.postings(query))
+                    .collect();
+                indexes.sort();
+                indexes.dedup();
+                indexes
+            }
+        }
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        push_number(&mut bytes, VERSION);
+        push_number(&mut bytes, self.count);
+        push_number(&mut bytes, self.words.len() as u32);
+        for (word, indexes) in &self.words {
+            push_number(&mut bytes, word.len() as u32);
+            bytes.extend_from_slice(word.as_bytes());
+            push_number(&mut bytes, indexes.len() as u32);
+            for index in indexes {
+                push_number(&mut bytes, *index);
+            }
+        }
+        bytes
+    }
+    /// Reads a word index from the bytes of its file. Returns `None` if it has another
+    /// version, or is corrupt.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut rest = bytes;
+        if take_number(&mut rest)? != VERSION {
+            return None;
+        }
+        let count = take_number(&mut rest)?;
+        let word_count = take_number(&mut rest)?;
+        let mut words = BTreeMap::new();
+        for _ in 0..word_count {
+            let length = take_number(&mut rest)? as usize;
+            let (word, tail) = rest.split_at_checked(length)?;
+            let word = String::from_utf8(word.to_vec()).ok()?;
+            rest = tail;
+            let index_count = take_number(&mut rest)?;
+            let indexes = (0..index_count)
+                .map(|_| take_number(&mut rest))
+                .collect::<Option<Vec<u32>>>()?;
+            words.insert(word, indexes);
+        }
+        Some(Self { count, words })
+    }
+}
+
+/// Adds a big-endian number to the end of the bytes.
+fn push_number(bytes: &mut Vec<u8>, number: u32) {
+    bytes.extend_from_slice(&number.to_be_bytes());
+}
+/// Takes a big-endian number from the front of the bytes.
+fn take_number(bytes: &mut &[u8]) -> Option<u32> {
+    let (number, rest) = bytes.split_first_chunk::<4>()?;
+    *bytes = rest;
+    Some(u32::from_be_bytes(*number))
+}
+
+/// Returns the path of the word index of the fortune file with the given `.dat` file.
+pub fn word_index_path(dat_path: &Path) -> PathBuf {
+    dat_path.with_extension(WORD_INDEX_EXTENSION)
+}
+
+/// Reads the word index of the fortune file with the given `.dat` file, if it has one that is
+/// usable: one that isn't corrupt, has as many fortunes as the `.dat` file, and isn't older than
+/// the text file.
+pub(crate) async fn read_word_index(
+    dat_path: &Path,
+    text_path: &Path,
+    count: usize,
+) -> Option<WordIndex> {
+    let path = word_index_path(dat_path);
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified());
+    if let (Ok(index_modified), Ok(text_modified)) = (modified(&path), modified(text_path)) {
+        if index_modified < text_modified {
+            return None;
+        }
+    }
+    let bytes = fs::read(&path).await.ok()?;
+    WordIndex::from_bytes(&bytes).filter(|word_index| word_index.count() == count)
+}
+
+/// Returns the indexes that are in all the lists, which are in order.
+fn intersection(lists: impl Iterator<Item = Vec<u32>>) -> Vec<u32> {
+    lists
+        .reduce(|common, list| {
+            common
+                .into_iter()
+                .filter(|index| list.binary_search(index).is_ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"Beam me up\n%\nSet phasers to stun\n%\nBeam them up, too\n%\n";
+
+    #[test]
+    fn build_indexes_each_fortune_once() {
+        let word_index = WordIndex::build(TEXT, &DataFile::parse(TEXT, None, None), None);
+        assert_eq!(word_index.count(), 3);
+        assert_eq!(word_index.indexes("beam"), [0, 2]);
+        assert_eq!(word_index.indexes("phasers"), [1]);
+        assert!(word_index.indexes("scotty").is_empty());
+        let query = Query::parse("beam up OR stun").unwrap();
+        assert_eq!(word_index.candidates(&query), [0, 1, 2]);
+    }
+    #[test]
+    fn to_bytes_and_from_bytes_round_trip() {
+        let word_index = WordIndex::build(TEXT, &DataFile::parse(TEXT, None, None), None);
+        let bytes = word_index.to_bytes();
+        assert_eq!(WordIndex::from_bytes(&bytes), Some(word_index));
+        // A truncated index is corrupt
+        assert_eq!(WordIndex::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+}
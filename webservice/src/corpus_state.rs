@@ -0,0 +1,108 @@
+/*
+ * Copyright (c) 2026 Paul Sobolik
+ * Created 2026-10-18
+ */
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use shared_library::{Corpus, FortuneOptions, FortuneSource, Result};
+
+/// The corpus that requests are served from, or the error that kept it from loading.
+/// It is replaced as a whole when the data files change, so a request always sees
+/// either the old corpus or the new one.
+pub struct CorpusState {
+    corpus: RwLock<Arc<Result<Corpus>>>,
+    source: Arc<dyn FortuneSource>,
+    options: FortuneOptions,
+}
+impl CorpusState {
+    /// Loads the corpus, and remembers the source and [FortuneOptions] to reload it with.
+    pub async fn load(source: Arc<dyn FortuneSource>, options: FortuneOptions) -> Self {
+        let corpus = Corpus::load_source(source.clone(), &options).await;
+        Self {
+            corpus: RwLock::new(Arc::new(corpus)),
+            source,
+            options,
+        }
+    }
+    pub fn corpus(&self) -> Arc<Result<Corpus>> {
+        self.corpus.read().unwrap().clone()
+    }
+    fn replace(&self, corpus: Result<Corpus>) {
+        *self.corpus.write().unwrap() = Arc::new(corpus);
+    }
+    /// Checks the source for changes every `interval`, and reloads the corpus when there
+    /// are any. If a reload fails, the old corpus is kept and it is tried again later.
+    pub async fn watch(self: Arc<Self>, interval: Duration) {
+        loop {
+            rocket::tokio::time::sleep(interval).await;
+            let current = self.corpus();
+            let reload = match current.as_ref() {
+                Ok(corpus) => corpus.is_stale().await,
+                Err(_) => true,
+            };
+            if !reload {
+                continue;
+            }
+            match Corpus::load_source(self.source.clone(), &self.options).await {
+                Ok(corpus) => {
+                    info!("Reloaded fortunes from {:?}", self.source.path());
+                    self.replace(Ok(corpus));
+                }
+                Err(error) if current.is_err() => self.replace(Err(error)),
+                Err(error) => warn!("Cannot reload fortunes: {}", error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use shared_library::{Collection, DirectorySource};
+
+    use super::*;
+
+    /// Returns the identifiers of the corpus's fortune files, or none if it didn't load.
+    fn collection_ids(corpus_state: &CorpusState) -> Vec<String> {
+        match corpus_state.corpus().as_ref() {
+            Ok(corpus) => corpus
+                .fortune_files(Collection::All)
+                .iter()
+                .map(|fortune_file_info| fortune_file_info.collection_id())
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    #[rocket::async_test]
+    async fn watch_reloads_the_corpus_when_the_files_change() {
+        let data_path =
+            std::env::temp_dir().join(format!("fortuneapi-corpus-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_path);
+        std::fs::create_dir_all(&data_path).unwrap();
+        std::fs::write(data_path.join("startrek"), "Kirk\n%\n").unwrap();
+        let source = DirectorySource::new(vec![PathBuf::from(&data_path)]).unwrap();
+        let corpus_state =
+            Arc::new(CorpusState::load(Arc::new(source), FortuneOptions::default()).await);
+        assert_eq!(collection_ids(&corpus_state), ["startrek"]);
+        let loaded = corpus_state.corpus();
+
+        let interval = Duration::from_millis(10);
+        rocket::tokio::spawn(corpus_state.clone().watch(interval));
+        // An unchanged folder isn't reloaded
+        rocket::tokio::time::sleep(interval * 5).await;
+        assert!(Arc::ptr_eq(&corpus_state.corpus(), &loaded));
+
+        std::fs::write(data_path.join("zippy"), "Yow!\n%\n").unwrap();
+        for _ in 0..100 {
+            if collection_ids(&corpus_state).len() == 2 {
+                break;
+            }
+            rocket::tokio::time::sleep(interval).await;
+        }
+        assert_eq!(collection_ids(&corpus_state), ["startrek", "zippy"]);
+        std::fs::remove_dir_all(&data_path).unwrap();
+    }
+}
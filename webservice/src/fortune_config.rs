@@ -8,7 +8,32 @@ use std::path::PathBuf;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct FortuneConfig {
-    pub data_path: PathBuf,
+    pub data_path: Option<PathBuf>,
+    /// More data folders, after `data_path`
+    #[serde(default)]
+    pub data_paths: Vec<PathBuf>,
+    /// How often to check the data folder for changes, in seconds
+    #[serde(default = "default_reload_interval")]
+    pub reload_interval: u64,
+    /// If true, fortune files that have no `.dat` file are ignored
+    #[serde(default)]
+    pub strict: bool,
+    /// If true, fortunes that aren't valid in their files' encodings are errors
+    #[serde(default)]
+    pub strict_encoding: bool,
+    /// If true, the fortune files are mapped into memory instead of read. A mapped file must
+    /// be replaced, not edited in place, or the server crashes
+    #[serde(default)]
+    pub mapped: bool,
+    /// The file that keeps each client's place in the files read with `/next`
+    #[serde(default = "default_state_path")]
+    pub state_path: PathBuf,
+    /// The most places kept in `state_path`; the least recently used are forgotten first
+    #[serde(default = "default_max_cursors")]
+    pub max_cursors: usize,
+    /// The most fortunes that `/search` returns, however many its `limit` asks for
+    #[serde(default = "default_max_search_results")]
+    pub max_search_results: usize,
 }
 impl FortuneConfig {
     pub fn new() -> Result<Self, ConfigError> {
@@ -17,5 +42,32 @@ impl FortuneConfig {
             .build()?;
         builder.try_deserialize()
     }
+    /// Returns the data folders, in order of precedence. If `FORTUNE_PATH` is set, it is used
+    /// instead of the configuration file's `data_path` and `data_paths`.
+    pub fn data_paths(&self) -> Vec<PathBuf> {
+        shared_library::fortune_path().unwrap_or_else(|| {
+            self.data_path
+                .iter()
+                .chain(&self.data_paths)
+                .cloned()
+                .collect()
+        })
+    }
 }
 const CONFIG_FILE: &str = "./Config.toml";
+
+fn default_reload_interval() -> u64 {
+    5
+}
+
+fn default_state_path() -> PathBuf {
+    PathBuf::from("./cursors.tsv")
+}
+
+fn default_max_cursors() -> usize {
+    shared_library::Cursors::DEFAULT_MAX_CURSORS
+}
+
+fn default_max_search_results() -> usize {
+    100
+}
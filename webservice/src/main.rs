@@ -4,43 +4,323 @@
  */
 mod fortune_config;
 mod cors;
+mod corpus_state;
 
 #[macro_use]
 extern crate rocket;
 
-use std::path::PathBuf;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use rocket::http::Status;
 use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
 
+use crate::corpus_state::CorpusState;
 use crate::fortune_config::FortuneConfig;
-use shared_library::{Fortune, FortuneStats};
+use shared_library::{
+    source_for_paths, Collection, Corpus, Cursors, Decoding, FormatOptions, Fortune, FortuneError,
+    FortuneOptions, FortuneStats, NaiveDate, Query, SearchOptions, Selection, Source,
+};
 
-#[get("/")]
-async fn index() -> Result<Json<Fortune>, Status> {
-    let path = get_data_path()?;
-    match shared_library::random_fortune(&path).await {
-        Ok(fortune) => Ok(Json(fortune)),
-        _ => Err(Status::InternalServerError),
+/// The longest name a client can give itself with `/next`'s `client` parameter
+const MAX_CLIENT_LENGTH: usize = 64;
+
+/// Returns a random fortune. If `equal` is true, every fortune file is equally likely
+/// to be chosen, regardless of its size. Offensive fortunes are only included if
+/// `offensive` is `only` or `all`. Only fortunes at least `min_len` and at most `max_len`
+/// bytes long are chosen, and only from the files in `category`, if it is given.
+/// The fortune's text is wrapped to `width` columns, if it is given.
+#[get("/?<equal>&<offensive>&<min_len>&<max_len>&<category>&<width>")]
+async fn index(
+    corpus_state: &State<Arc<CorpusState>>,
+    equal: Option<bool>,
+    offensive: Option<&str>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    category: Option<&str>,
+    width: Option<usize>,
+) -> Result<Json<Fortune>, Status> {
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let mut fortune_options = FortuneOptions::default();
+    if equal.unwrap_or_default() {
+        fortune_options.set_selection(Selection::Equal);
+    }
+    fortune_options
+        .set_collection(get_collection(offensive)?)
+        .set_min_length(min_len)
+        .set_max_length(max_len)
+        .set_sources(get_sources(category));
+    match corpus.random_fortune(&fortune_options).await {
+        Ok(fortune) => Ok(Json(format_fortune(fortune, width)?)),
+        Err(error) => Err(error_status(&error)),
+    }
+}
+/// Returns information about the fortune files, or only those in `category`, if it is given.
+#[get("/info?<offensive>&<category>")]
+async fn info(
+    corpus_state: &State<Arc<CorpusState>>,
+    offensive: Option<&str>,
+    category: Option<&str>,
+) -> Result<Json<Vec<FortuneStats>>, Status> {
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let mut fortune_options = FortuneOptions::default();
+    fortune_options
+        .set_collection(get_collection(offensive)?)
+        .set_sources(get_sources(category));
+    match corpus.fortune_stats(&fortune_options) {
+        Ok(fortune_stats) => Ok(Json(fortune_stats)),
+        Err(error) => Err(error_status(&error)),
+    }
+}
+
+/// A fortune, with the identifiers of the fortunes before and after it in its file.
+#[derive(Serialize)]
+struct FortunePage {
+    #[serde(flatten)]
+    fortune: Fortune,
+    previous: Option<String>,
+    next: Option<String>,
+}
+/// Returns the fortune with the given identifier, e.g. `/fortune/startrek/12`,
+/// or `/fortune/off/limerick/3` for an offensive one. The fortune is wrapped to `width`
+/// columns, if it is given.
+#[get("/fortune/<id..>?<width>")]
+async fn fortune(
+    corpus_state: &State<Arc<CorpusState>>,
+    id: PathBuf,
+    width: Option<usize>,
+) -> Result<Json<FortunePage>, Status> {
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let id = path_id(&id);
+    let (previous, next) = corpus
+        .adjacent_ids(&id)
+        .map_err(|error| error_status(&error))?;
+    match corpus.fortune_by_id(&id).await {
+        Ok(fortune) => Ok(Json(FortunePage {
+            fortune: format_fortune(fortune, width)?,
+            previous,
+            next,
+        })),
+        Err(error) => Err(error_status(&error)),
+    }
+}
+/// Returns the next fortune, in order, from the fortune file with the given identifier,
+/// e.g. `/next/startrek`. Each client has its own place in each file. A client is identified
+/// by `client`, which can be up to 64 characters, or by its IP address if that isn't given.
+/// Behind a proxy, the address is taken from the `X-Real-IP` header. The fortune is wrapped
+/// to `width` columns, if it is given.
+#[get("/next/<id..>?<client>&<width>")]
+async fn next(
+    corpus_state: &State<Arc<CorpusState>>,
+    cursors: &State<Cursors>,
+    id: PathBuf,
+    client: Option<&str>,
+    width: Option<usize>,
+    client_ip: Option<IpAddr>,
+) -> Result<Json<Fortune>, Status> {
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let reader = match (client, client_ip) {
+        (Some(client), _) if client.chars().count() > MAX_CLIENT_LENGTH => {
+            return Err(Status::BadRequest)
+        }
+        (Some(client), _) => client.to_owned(),
+        (None, Some(client_ip)) => client_ip.to_string(),
+        (None, None) => return Err(Status::BadRequest),
+    };
+    match corpus.next_fortune(&path_id(&id), cursors, &reader).await {
+        Ok(fortune) => Ok(Json(format_fortune(fortune, width)?)),
+        Err(error) => Err(error_status(&error)),
+    }
+}
+/// Returns the fortune of the day, which is the same all day. If `user` is given, each
+/// user gets their own. If `date` (YYYY-MM-DD) is given, returns that day's fortune.
+/// If `category` is given, the fortune comes from the files in it. The fortune is wrapped to
+/// `width` columns, if it is given.
+#[get("/today?<user>&<date>&<offensive>&<category>&<width>")]
+async fn today(
+    corpus_state: &State<Arc<CorpusState>>,
+    user: Option<&str>,
+    date: Option<&str>,
+    offensive: Option<&str>,
+    category: Option<&str>,
+    width: Option<usize>,
+) -> Result<Json<Fortune>, Status> {
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let date = match date {
+        Some(date) => Some(date.parse::<NaiveDate>().map_err(|_| Status::BadRequest)?),
+        None => None,
+    };
+    let mut fortune_options = FortuneOptions::default();
+    fortune_options
+        .set_collection(get_collection(offensive)?)
+        .set_sources(get_sources(category));
+    match corpus
+        .fortune_of_the_day(&fortune_options, date, user)
+        .await
+    {
+        Ok(fortune) => Ok(Json(format_fortune(fortune, width)?)),
+        Err(error) => Err(error_status(&error)),
+    }
+}
+/// The parameters of a search. At least one of `q`, `query` and `author` must be given.
+#[derive(FromForm)]
+struct SearchParams<'r> {
+    q: Option<&'r str>,
+    i: Option<bool>,
+    query: Option<&'r str>,
+    author: Option<&'r str>,
+    file: Vec<String>,
+    category: Vec<String>,
+    offensive: Option<&'r str>,
+    width: Option<usize>,
+    limit: Option<usize>,
+}
+/// Returns the fortunes that match the regular expression `q`, and the word `query`,
+/// which uses the fortune files' word indexes. If `i` is true, the regular expression ignores
+/// case. The search can be limited to some of the fortune files by giving their names as
+/// `file`, or their categories as `category`, both of which can be repeated, and to the
+/// fortunes by an `author`. The fortunes are wrapped to `width` columns, if it is given.
+/// At most `limit` fortunes are returned, and never more than the configured maximum.
+#[get("/search?<params..>")]
+async fn search(
+    corpus_state: &State<Arc<CorpusState>>,
+    config: &State<FortuneConfig>,
+    params: SearchParams<'_>,
+) -> Result<Json<Vec<Fortune>>, Status> {
+    // Without anything to search for, every fortune would match
+    if params.q.is_none() && params.query.is_none() && params.author.is_none() {
+        return Err(Status::BadRequest);
     }
+    let corpus = corpus_state.corpus();
+    let corpus = get_corpus(&corpus)?;
+    let mut files = params.file;
+    files.extend(params.category);
+    let query = params
+        .query
+        .map(Query::parse)
+        .transpose()
+        .map_err(|error| error_status(&error))?;
+    let mut search_options = SearchOptions::new(params.q.unwrap_or_default());
+    search_options
+        .set_case_insensitive(params.i.unwrap_or_default())
+        .set_query(query)
+        .set_author(params.author.map(str::to_owned))
+        .set_files(files)
+        .set_collection(get_collection(params.offensive)?);
+    let mut search = corpus
+        .search(&search_options)
+        .map_err(|error| error_status(&error))?;
+    let limit = params
+        .limit
+        .unwrap_or(usize::MAX)
+        .min(config.max_search_results);
+    let mut fortunes = vec![];
+    while fortunes.len() < limit {
+        let Some(fortune) = search.next_match().await else {
+            break;
+        };
+        let fortune = fortune.map_err(|error| error_status(&error))?;
+        fortunes.push(format_fortune(fortune, params.width)?);
+    }
+    Ok(Json(fortunes))
 }
-#[get("/info")]
-async fn info() -> Result<Json<Vec<FortuneStats>>, Status> {
-    let path = get_data_path()?;
-    match shared_library::fortune_stats(&path).await {
-        Ok(fortune_file_infos) => Ok(Json(fortune_file_infos)),
-        _ => Err(Status::InternalServerError),
+
+/// Lays out the fortune's text to fit `width` columns, if it is given, which must not be 0.
+fn format_fortune(mut fortune: Fortune, width: Option<usize>) -> Result<Fortune, Status> {
+    match width {
+        Some(0) => Err(Status::BadRequest),
+        Some(width) => {
+            fortune.format(&FormatOptions::new(Some(width)));
+            Ok(fortune)
+        }
+        None => Ok(fortune),
     }
 }
+/// Returns the fortune identifier that the segments of a route's path make up.
+fn path_id(path: &Path) -> String {
+    path.iter()
+        .map(|segment| segment.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn get_corpus(corpus: &shared_library::Result<Corpus>) -> Result<&Corpus, Status> {
+    corpus.as_ref().map_err(error_status)
+}
 
-fn get_data_path() -> Result<PathBuf, Status> {
-    match FortuneConfig::new() {
-        Ok(config) => Ok(config.data_path),
-        _ => Err(Status::InternalServerError),
+/// Returns the sources that limit the choice to the given category, if there is one.
+fn get_sources(category: Option<&str>) -> Vec<Source> {
+    category
+        .map(|category| vec![Source::new(category.to_owned(), None)])
+        .unwrap_or_default()
+}
+
+fn get_collection(offensive: Option<&str>) -> Result<Collection, Status> {
+    match offensive {
+        None | Some("none") => Ok(Collection::Normal),
+        Some("only") => Ok(Collection::Offensive),
+        Some("all") => Ok(Collection::All),
+        _ => Err(Status::BadRequest),
+    }
+}
+
+/// Returns the HTTP status that best describes the error.
+fn error_status(error: &FortuneError) -> Status {
+    match error {
+        FortuneError::DataFolder { .. } => Status::ServiceUnavailable,
+        FortuneError::EmptyCorpus { .. }
+        | FortuneError::UnknownSource { .. }
+        | FortuneError::NoSuchFortune { .. } => Status::NotFound,
+        FortuneError::InvalidSource { .. }
+        | FortuneError::PercentOver { .. }
+        | FortuneError::InvalidPattern { .. }
+        | FortuneError::InvalidQuery { .. }
+        | FortuneError::InvalidId { .. }
+        | FortuneError::NotOrdered { .. } => Status::BadRequest,
+        FortuneError::BadVersion { .. }
+        | FortuneError::TruncatedHeader { .. }
+        | FortuneError::OffsetOutOfRange { .. }
+        | FortuneError::MissingTextFile { .. }
+        | FortuneError::InvalidEncoding { .. }
+        | FortuneError::UnknownEncoding { .. }
+        | FortuneError::InvalidManifest { .. }
+        | FortuneError::Io { .. } => Status::InternalServerError,
     }
 }
 
 #[launch]
-fn rocket() -> _ {
-    rocket::build().mount("/", routes![index, info]).attach(cors::Cors)
+async fn rocket() -> _ {
+    let config = FortuneConfig::new().expect("Cannot read the configuration file");
+    // Load the fortunes once, and reload them in the background when they change.
+    // If configured, map the fortune files into memory, so requests are served without file I/O
+    let mut options = FortuneOptions::default();
+    options.set_strict(config.strict).set_mapped(config.mapped);
+    if config.strict_encoding {
+        options.set_decoding(Decoding::Strict);
+    }
+    // A single data path can be an archive of the fortune files instead of a folder
+    let source = source_for_paths(&config.data_paths()).expect("No data folders are configured");
+    let corpus_state = Arc::new(CorpusState::load(source, options).await);
+    rocket::tokio::spawn(
+        corpus_state
+            .clone()
+            .watch(Duration::from_secs(config.reload_interval)),
+    );
+    let mut cursors = Cursors::new(config.state_path.clone());
+    cursors.set_max_cursors(config.max_cursors);
+    rocket::build()
+        .manage(corpus_state)
+        .manage(cursors)
+        .manage(config)
+        .mount("/", routes![index, info, search, today, fortune, next])
+        .attach(cors::Cors)
 }